@@ -1,14 +1,336 @@
+use ethers::providers::Middleware;
 pub use ethers::types::{Address, Chain};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::bindings::{UNISWAP_V2_ROUTER, UNISWAP_V3_ROUTER};
+use crate::config::{get_addressbook_path, get_chain_config, is_chain_supported};
+use crate::whitelist;
 
 const CONTRACTS_JSON: &str = include_str!("./addressbook.json");
 
-static ADDRESSBOOK: Lazy<HashMap<String, HashMap<String, Address>>> =
-    Lazy::new(|| serde_json::from_str(CONTRACTS_JSON).unwrap());
+/// Contract addresses keyed by name, then by chain name or id.
+type RawAddressbook = HashMap<String, HashMap<String, Address>>;
+
+/// Addressbook entries in file layout, ordered so that written files are stable.
+pub type ProposedEntries = BTreeMap<String, BTreeMap<String, Address>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    BuiltIn,
+    User,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in"),
+            Source::User => write!(f, "user"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    pub chain: Chain,
+    pub address: Address,
+    pub source: Source,
+}
+
+/// Built-in entries layered with the user addressbook, keyed by normalized
+/// name and chain. The user file may add entries, but only replaces a
+/// built-in address when it is signed by the whitelist admin key.
+static ADDRESSBOOK: Lazy<HashMap<String, HashMap<Chain, Entry>>> = Lazy::new(|| {
+    let mut addressbook = HashMap::new();
+    merge(
+        &mut addressbook,
+        serde_json::from_str(CONTRACTS_JSON).unwrap(),
+        Source::BuiltIn,
+        true,
+    );
+    let path = get_addressbook_path();
+    if let Ok(content) = fs::read_to_string(&path) {
+        match serde_json::from_str(&content) {
+            Ok(user) => {
+                let signed = whitelist::verify_signature(&path.to_string_lossy(), &content);
+                merge(&mut addressbook, user, Source::User, signed.is_ok());
+            }
+            Err(e) => eprintln!("Warning: ignoring {}: {}", path.display(), e),
+        }
+    }
+    addressbook
+});
+
+fn merge(
+    addressbook: &mut HashMap<String, HashMap<Chain, Entry>>,
+    raw: RawAddressbook,
+    source: Source,
+    signed: bool,
+) {
+    for (name, addresses) in raw {
+        for (chain_name, address) in addresses {
+            let Some(chain) = parse_chain_key(&chain_name) else {
+                eprintln!(
+                    "Warning: unknown chain {} for addressbook entry {}",
+                    chain_name, name
+                );
+                continue;
+            };
+            let entries = addressbook.entry(normalize_key(&name)).or_default();
+            if let Some(existing) = entries.get(&chain) {
+                if !signed && existing.address != address {
+                    eprintln!(
+                        "WARNING: refusing the unsigned override of built-in addressbook entry {} on {}: {:?} instead of {:?}. Run `addressbook sign` to accept it.",
+                        name, chain, address, existing.address
+                    );
+                    continue;
+                }
+            }
+            entries.insert(
+                chain,
+                Entry {
+                    name: name.clone(),
+                    chain,
+                    address,
+                    source,
+                },
+            );
+        }
+    }
+}
+
+/// Normalizes a name so that `uniswap_v3_swap_router`, `uniswapV3SwapRouter`
+/// and `Uniswap-V3-Swap-Router` refer to the same entry.
+pub fn normalize_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn parse_chain_key(name: &str) -> Option<Chain> {
+    if let Ok(chain_id) = name.parse::<u64>() {
+        return Chain::try_from(chain_id).ok();
+    }
+    Chain::from_str(&name.to_lowercase().replace('_', "-")).ok()
+}
 
 pub fn contract_address<S: Into<String>>(name: S, chain: Chain) -> Option<Address> {
     ADDRESSBOOK
-        .get(&name.into())
-        .and_then(|addresses| addresses.get(&chain.to_string().to_lowercase()).cloned())
+        .get(&normalize_key(&name.into()))
+        .and_then(|addresses| addresses.get(&chain))
+        .map(|entry| entry.address)
+}
+
+/// Name of the addressbook entry for `address` on `chain`, if any.
+pub fn contract_name(address: Address, chain: Chain) -> Option<&'static str> {
+    entries()
+        .into_iter()
+        .find(|entry| entry.chain == chain && entry.address == address)
+        .map(|entry| entry.name.as_str())
+}
+
+/// Every entry of the layered addressbook, sorted by chain and name.
+pub fn entries() -> Vec<&'static Entry> {
+    let mut entries: Vec<&Entry> = ADDRESSBOOK
+        .values()
+        .flat_map(|addresses| addresses.values())
+        .collect();
+    entries.sort_by_key(|entry| (entry.chain as u64, normalize_key(&entry.name)));
+    entries
+}
+
+/// Signs the user addressbook with the admin key, so that its overrides of
+/// built-in entries are applied.
+pub fn sign() -> Result<(), Box<dyn std::error::Error>> {
+    let path = get_addressbook_path();
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    serde_json::from_str::<RawAddressbook>(&content)
+        .map_err(|e| format!("{} is not a valid addressbook: {}", path.display(), e))?;
+    whitelist::sign_file(&path.to_string_lossy(), &content)
+}
+
+/// Checks that every entry (optionally only on `chain`) has deployed bytecode.
+/// Entries on chains without an RPC configuration are reported as skipped.
+pub async fn verify(chain: Option<Chain>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut providers = HashMap::new();
+    let mut missing = 0;
+    for entry in entries() {
+        if chain.is_some_and(|chain| chain != entry.chain) {
+            continue;
+        }
+        let label = format!(
+            "{} on {} ({:?}, {})",
+            entry.name, entry.chain, entry.address, entry.source
+        );
+        if !is_chain_supported(entry.chain) {
+            println!("- skipped, no RPC configured: {}", label);
+            continue;
+        }
+        let provider = match providers.get(&entry.chain) {
+            Some(provider) => Arc::clone(provider),
+            None => {
+                let provider = get_chain_config(entry.chain).await.http;
+                providers.insert(entry.chain, Arc::clone(&provider));
+                provider
+            }
+        };
+        let code = provider.get_code(entry.address, None).await?;
+        if code.is_empty() {
+            missing += 1;
+            println!("! no code deployed: {}", label);
+        } else {
+            println!("  ok: {}", label);
+        }
+    }
+    if missing > 0 {
+        return Err(format!("{} addressbook entries have no deployed code", missing).into());
+    }
+    Ok(())
+}
+
+/// Routers known on other chains that are also deployed behind `provider`
+/// and wrap `weth`. Uniswap deploys its routers at the same address on most
+/// chains.
+async fn routers_wrapping<M: Middleware + 'static>(
+    provider: &Arc<M>,
+    weth: Address,
+) -> Result<Vec<Address>, Box<dyn std::error::Error>> {
+    let router_keys = [
+        "uniswap_v2_router",
+        "uniswap_v3_swap_router",
+        "uniswap_v3_swap_router02",
+    ]
+    .map(normalize_key);
+    let mut candidates: Vec<Address> = entries()
+        .into_iter()
+        .filter(|entry| router_keys.contains(&normalize_key(&entry.name)))
+        .map(|entry| entry.address)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+
+    let mut routers = Vec::new();
+    for candidate in candidates {
+        if provider.get_code(candidate, None).await?.is_empty() {
+            continue;
+        }
+        let wrapped = match UNISWAP_V2_ROUTER::new(candidate, Arc::clone(provider))
+            .weth()
+            .call()
+            .await
+        {
+            Ok(wrapped) => Some(wrapped),
+            Err(_) => UNISWAP_V3_ROUTER::new(candidate, Arc::clone(provider))
+                .weth9()
+                .call()
+                .await
+                .ok(),
+        };
+        if wrapped == Some(weth) {
+            println!(
+                "{:?} is deployed on this chain and wraps {:?}",
+                candidate, weth
+            );
+            routers.push(candidate);
+        }
+    }
+    Ok(routers)
+}
+
+/// Discovers the Uniswap factories and WETH of `chain` from router addresses,
+/// starting from `routers`, the routers already known for the chain and,
+/// given `weth`, the routers of other chains that wrap it on `chain`. The
+/// router version is told apart by `WETH()` (V2) or `WETH9()` (V3). Every
+/// discovered address must have deployed code, and all routers (and `weth`,
+/// if given) must agree on the WETH address.
+pub async fn discover(
+    chain: Chain,
+    routers: &[Address],
+    weth: Option<Address>,
+) -> Result<ProposedEntries, Box<dyn std::error::Error>> {
+    let provider = get_chain_config(chain).await.http;
+
+    let mut seeds = routers.to_vec();
+    for name in ["uniswap_v2_router", "uniswap_v3_swap_router"] {
+        if let Some(address) = contract_address(name, chain) {
+            if !seeds.contains(&address) {
+                seeds.push(address);
+            }
+        }
+    }
+    if let Some(weth) = weth {
+        for router in routers_wrapping(&provider, weth).await? {
+            if !seeds.contains(&router) {
+                seeds.push(router);
+            }
+        }
+    }
+    if seeds.is_empty() {
+        return Err(format!(
+            "No Uniswap router known on {}, pass one with --router",
+            chain
+        )
+        .into());
+    }
+
+    let mut discovered: Vec<(&str, Address)> = Vec::new();
+    if let Some(weth) = weth {
+        discovered.push(("weth", weth));
+    }
+    for router in seeds {
+        let v2_router = UNISWAP_V2_ROUTER::new(router, Arc::clone(&provider));
+        if let Ok(weth) = v2_router.weth().call().await {
+            let factory = v2_router.factory().call().await?;
+            println!("{:?} is a Uniswap V2 router", router);
+            discovered.extend([
+                ("uniswapV2Router", router),
+                ("uniswapV2Factory", factory),
+                ("weth", weth),
+            ]);
+            continue;
+        }
+        let v3_router = UNISWAP_V3_ROUTER::new(router, Arc::clone(&provider));
+        if let Ok(weth) = v3_router.weth9().call().await {
+            let factory = v3_router.factory().call().await?;
+            println!("{:?} is a Uniswap V3 router", router);
+            discovered.extend([
+                ("uniswapV3SwapRouter", router),
+                ("uniswapV3Factory", factory),
+                ("weth", weth),
+            ]);
+            continue;
+        }
+        println!(
+            "! {:?} answers neither WETH() nor WETH9(), it is not a Uniswap router",
+            router
+        );
+    }
+
+    let chain_key = chain.to_string();
+    let mut proposed = ProposedEntries::new();
+    for (name, address) in discovered {
+        let chains = proposed.entry(name.to_string()).or_default();
+        match chains.get(&chain_key) {
+            Some(existing) if *existing != address => {
+                return Err(format!(
+                    "Conflicting {} addresses on {}: {:?} and {:?}",
+                    name, chain, existing, address
+                )
+                .into())
+            }
+            Some(_) => continue,
+            None => {}
+        }
+        if provider.get_code(address, None).await?.is_empty() {
+            return Err(format!("{} {:?} has no deployed code on {}", name, address, chain).into());
+        }
+        chains.insert(chain_key.clone(), address);
+    }
+    Ok(proposed)
 }
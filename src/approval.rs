@@ -0,0 +1,79 @@
+//! How token approvals are granted: exact amounts, unlimited allowances or
+//! EIP-2612 permit signatures.
+
+use ethers::abi::Token;
+use ethers::types::{Address, Signature, H256, U256};
+use ethers::utils::keccak256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ApprovalMode {
+    /// Approve exactly the amount about to be spent
+    #[default]
+    Exact,
+    /// Approve the maximum amount, so later runs need no approval
+    Unlimited,
+    /// Sign an EIP-2612 permit when the token and the spender support it,
+    /// approving the exact amount otherwise
+    Permit,
+}
+
+impl ApprovalMode {
+    /// Allowance to grant when `needed` is about to be spent.
+    pub fn amount(self, needed: U256) -> U256 {
+        match self {
+            ApprovalMode::Unlimited => U256::MAX,
+            ApprovalMode::Exact | ApprovalMode::Permit => needed,
+        }
+    }
+}
+
+/// Allowances from this amount up are treated as unlimited: it exceeds any
+/// real token supply, and tokens that decrement a maximum allowance on
+/// transfer leave it above this.
+pub fn is_unlimited(allowance: U256) -> bool {
+    allowance >= U256::from(u128::MAX)
+}
+
+/// Topic of the ERC20 `Approval(owner, spender, value)` event.
+pub fn approval_topic() -> H256 {
+    H256::from(keccak256("Approval(address,address,uint256)"))
+}
+
+/// Type hash of the EIP-2612 `Permit` struct.
+pub fn permit_typehash() -> [u8; 32] {
+    keccak256("Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)")
+}
+
+/// Digest to sign for an EIP-2612 permit letting `spender` move `value` of
+/// `owner`'s tokens, under the token's own domain separator.
+pub fn permit_digest(
+    domain_separator: [u8; 32],
+    typehash: [u8; 32],
+    owner: Address,
+    spender: Address,
+    value: U256,
+    nonce: U256,
+    deadline: U256,
+) -> H256 {
+    let struct_hash = keccak256(ethers::abi::encode(&[
+        Token::FixedBytes(typehash.to_vec()),
+        Token::Address(owner),
+        Token::Address(spender),
+        Token::Uint(value),
+        Token::Uint(nonce),
+        Token::Uint(deadline),
+    ]));
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(b"\x19\x01");
+    message.extend_from_slice(&domain_separator);
+    message.extend_from_slice(&struct_hash);
+    H256::from(keccak256(message))
+}
+
+/// The `v`, `r` and `s` arguments of a permit call.
+pub fn split_signature(signature: &Signature) -> (u8, [u8; 32], [u8; 32]) {
+    let (mut r, mut s) = ([0u8; 32], [0u8; 32]);
+    signature.r.to_big_endian(&mut r);
+    signature.s.to_big_endian(&mut s);
+    (signature.v as u8, r, s)
+}
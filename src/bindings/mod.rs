@@ -1,13 +1,20 @@
 pub use self::uniswap_v2_factory::UNISWAP_V2_FACTORY;
 pub use self::uniswap_v2_pool::UNISWAP_V2_POOL;
 pub use self::uniswap_v2_router::UNISWAP_V2_ROUTER;
+pub use self::uniswap_v3_factory::UNISWAP_V3_FACTORY;
 pub use self::uniswap_v3_pool::UNISWAP_V3_POOL;
+pub use self::uniswap_v3_position_manager::UNISWAP_V3_POSITION_MANAGER;
+pub use self::uniswap_v3_quoter_v2::UNISWAP_V3_QUOTER_V2;
 pub use self::uniswap_v3_router::UNISWAP_V3_ROUTER;
 pub use self::weth::WETH;
 
 pub mod uniswap_v2_factory;
 pub mod uniswap_v2_pool;
 pub mod uniswap_v2_router;
+pub mod uniswap_v3_factory;
 pub mod uniswap_v3_pool;
+pub mod uniswap_v3_position_manager;
+pub mod uniswap_v3_quoter_v2;
 pub mod uniswap_v3_router;
+pub mod usdt;
 pub mod weth;
@@ -14,110 +14,136 @@ pub mod uniswap_v3_factory {
     fn __abi() -> ::ethers::core::abi::Abi {
         ::ethers::core::abi::ethabi::Contract {
             constructor: ::core::option::Option::Some(::ethers::core::abi::ethabi::Constructor {
-                inputs: ::std::vec![
-                    ::ethers::core::abi::ethabi::Param {
-                        name: ::std::borrow::ToOwned::to_owned("_feeToSetter"),
-                        kind: ::ethers::core::abi::ethabi::ParamType::Address,
-                        internal_type: ::core::option::Option::Some(
-                            ::std::borrow::ToOwned::to_owned("address"),
-                        ),
-                    },
-                ],
+                inputs: ::std::vec![],
             }),
             functions: ::core::convert::From::from([
                 (
-                    ::std::borrow::ToOwned::to_owned("allPairs"),
+                    ::std::borrow::ToOwned::to_owned("createPool"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("allPairs"),
+                            name: ::std::borrow::ToOwned::to_owned("createPool"),
                             inputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::string::String::new(),
-                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
-                                        256usize,
+                                    name: ::std::borrow::ToOwned::to_owned("tokenA"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("tokenB"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
                                     ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("fee"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
                                     internal_type: ::core::option::Option::Some(
-                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                        ::std::borrow::ToOwned::to_owned("uint24"),
                                     ),
                                 },
                             ],
                             outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::string::String::new(),
+                                    name: ::std::borrow::ToOwned::to_owned("pool"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
                             ],
-                            constant: ::core::option::Option::Some(true),
-                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
                         },
                     ],
                 ),
                 (
-                    ::std::borrow::ToOwned::to_owned("allPairsLength"),
+                    ::std::borrow::ToOwned::to_owned("enableFeeAmount"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("allPairsLength"),
-                            inputs: ::std::vec![],
-                            outputs: ::std::vec![
+                            name: ::std::borrow::ToOwned::to_owned("enableFeeAmount"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("fee"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint24"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("tickSpacing"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Int(24usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("int24"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("feeAmountTickSpacing"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned(
+                                "feeAmountTickSpacing",
+                            ),
+                            inputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
                                     name: ::std::string::String::new(),
-                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
-                                        256usize,
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint24"),
                                     ),
+                                },
+                            ],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Int(24usize),
                                     internal_type: ::core::option::Option::Some(
-                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                        ::std::borrow::ToOwned::to_owned("int24"),
                                     ),
                                 },
                             ],
-                            constant: ::core::option::Option::Some(true),
+                            constant: ::core::option::Option::None,
                             state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
                         },
                     ],
                 ),
                 (
-                    ::std::borrow::ToOwned::to_owned("createPair"),
+                    ::std::borrow::ToOwned::to_owned("getPool"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("createPair"),
+                            name: ::std::borrow::ToOwned::to_owned("getPool"),
                             inputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::borrow::ToOwned::to_owned("tokenA"),
+                                    name: ::std::string::String::new(),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::borrow::ToOwned::to_owned("tokenB"),
+                                    name: ::std::string::String::new(),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
-                            ],
-                            outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::borrow::ToOwned::to_owned("pair"),
-                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
                                     internal_type: ::core::option::Option::Some(
-                                        ::std::borrow::ToOwned::to_owned("address"),
+                                        ::std::borrow::ToOwned::to_owned("uint24"),
                                     ),
                                 },
                             ],
-                            constant: ::core::option::Option::Some(false),
-                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
-                        },
-                    ],
-                ),
-                (
-                    ::std::borrow::ToOwned::to_owned("feeTo"),
-                    ::std::vec![
-                        ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("feeTo"),
-                            inputs: ::std::vec![],
                             outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
                                     name: ::std::string::String::new(),
@@ -127,16 +153,16 @@ pub mod uniswap_v3_factory {
                                     ),
                                 },
                             ],
-                            constant: ::core::option::Option::Some(true),
+                            constant: ::core::option::Option::None,
                             state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
                         },
                     ],
                 ),
                 (
-                    ::std::borrow::ToOwned::to_owned("feeToSetter"),
+                    ::std::borrow::ToOwned::to_owned("owner"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("feeToSetter"),
+                            name: ::std::borrow::ToOwned::to_owned("owner"),
                             inputs: ::std::vec![],
                             outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
@@ -147,54 +173,67 @@ pub mod uniswap_v3_factory {
                                     ),
                                 },
                             ],
-                            constant: ::core::option::Option::Some(true),
+                            constant: ::core::option::Option::None,
                             state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
                         },
                     ],
                 ),
                 (
-                    ::std::borrow::ToOwned::to_owned("getPair"),
+                    ::std::borrow::ToOwned::to_owned("parameters"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("getPair"),
-                            inputs: ::std::vec![
+                            name: ::std::borrow::ToOwned::to_owned("parameters"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::string::String::new(),
+                                    name: ::std::borrow::ToOwned::to_owned("factory"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::string::String::new(),
+                                    name: ::std::borrow::ToOwned::to_owned("token0"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
-                            ],
-                            outputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::string::String::new(),
+                                    name: ::std::borrow::ToOwned::to_owned("token1"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
                                     ),
                                 },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("fee"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint24"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("tickSpacing"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Int(24usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("int24"),
+                                    ),
+                                },
                             ],
-                            constant: ::core::option::Option::Some(true),
+                            constant: ::core::option::Option::None,
                             state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
                         },
                     ],
                 ),
                 (
-                    ::std::borrow::ToOwned::to_owned("setFeeTo"),
+                    ::std::borrow::ToOwned::to_owned("setOwner"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("setFeeTo"),
+                            name: ::std::borrow::ToOwned::to_owned("setOwner"),
                             inputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::borrow::ToOwned::to_owned("_feeTo"),
+                                    name: ::std::borrow::ToOwned::to_owned("_owner"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     internal_type: ::core::option::Option::Some(
                                         ::std::borrow::ToOwned::to_owned("address"),
@@ -202,38 +241,60 @@ pub mod uniswap_v3_factory {
                                 },
                             ],
                             outputs: ::std::vec![],
-                            constant: ::core::option::Option::Some(false),
+                            constant: ::core::option::Option::None,
                             state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
                         },
                     ],
                 ),
+            ]),
+            events: ::core::convert::From::from([
                 (
-                    ::std::borrow::ToOwned::to_owned("setFeeToSetter"),
+                    ::std::borrow::ToOwned::to_owned("FeeAmountEnabled"),
                     ::std::vec![
-                        ::ethers::core::abi::ethabi::Function {
-                            name: ::std::borrow::ToOwned::to_owned("setFeeToSetter"),
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("FeeAmountEnabled"),
                             inputs: ::std::vec![
-                                ::ethers::core::abi::ethabi::Param {
-                                    name: ::std::borrow::ToOwned::to_owned("_feeToSetter"),
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("fee"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
+                                    indexed: true,
+                                },
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("tickSpacing"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Int(24usize),
+                                    indexed: true,
+                                },
+                            ],
+                            anonymous: false,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("OwnerChanged"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("OwnerChanged"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("oldOwner"),
                                     kind: ::ethers::core::abi::ethabi::ParamType::Address,
-                                    internal_type: ::core::option::Option::Some(
-                                        ::std::borrow::ToOwned::to_owned("address"),
-                                    ),
+                                    indexed: true,
+                                },
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("newOwner"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    indexed: true,
                                 },
                             ],
-                            outputs: ::std::vec![],
-                            constant: ::core::option::Option::Some(false),
-                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                            anonymous: false,
                         },
                     ],
                 ),
-            ]),
-            events: ::core::convert::From::from([
                 (
-                    ::std::borrow::ToOwned::to_owned("PairCreated"),
+                    ::std::borrow::ToOwned::to_owned("PoolCreated"),
                     ::std::vec![
                         ::ethers::core::abi::ethabi::Event {
-                            name: ::std::borrow::ToOwned::to_owned("PairCreated"),
+                            name: ::std::borrow::ToOwned::to_owned("PoolCreated"),
                             inputs: ::std::vec![
                                 ::ethers::core::abi::ethabi::EventParam {
                                     name: ::std::borrow::ToOwned::to_owned("token0"),
@@ -246,15 +307,18 @@ pub mod uniswap_v3_factory {
                                     indexed: true,
                                 },
                                 ::ethers::core::abi::ethabi::EventParam {
-                                    name: ::std::borrow::ToOwned::to_owned("pair"),
-                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    name: ::std::borrow::ToOwned::to_owned("fee"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(24usize),
+                                    indexed: true,
+                                },
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("tickSpacing"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Int(24usize),
                                     indexed: false,
                                 },
                                 ::ethers::core::abi::ethabi::EventParam {
-                                    name: ::std::string::String::new(),
-                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
-                                        256usize,
-                                    ),
+                                    name: ::std::borrow::ToOwned::to_owned("pool"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
                                     indexed: false,
                                 },
                             ],
@@ -311,99 +375,117 @@ pub mod uniswap_v3_factory {
                 ),
             )
         }
-        ///Calls the contract's `allPairs` (0x1e3dd18b) function
-        pub fn all_pairs(
+        ///Calls the contract's `createPool` (0xa1671295) function
+        pub fn create_pool(
             &self,
-            p0: ::ethers::core::types::U256,
+            token_a: ::ethers::core::types::Address,
+            token_b: ::ethers::core::types::Address,
+            fee: u32,
         ) -> ::ethers::contract::builders::ContractCall<
             M,
             ::ethers::core::types::Address,
         > {
             self.0
-                .method_hash([30, 61, 209, 139], p0)
+                .method_hash([161, 103, 18, 149], (token_a, token_b, fee))
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `allPairsLength` (0x574f2ba3) function
-        pub fn all_pairs_length(
+        ///Calls the contract's `enableFeeAmount` (0x8a7c195f) function
+        pub fn enable_fee_amount(
             &self,
-        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            fee: u32,
+            tick_spacing: i32,
+        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
             self.0
-                .method_hash([87, 79, 43, 163], ())
+                .method_hash([138, 124, 25, 95], (fee, tick_spacing))
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `createPair` (0xc9c65396) function
-        pub fn create_pair(
+        ///Calls the contract's `feeAmountTickSpacing` (0x22afcccb) function
+        pub fn fee_amount_tick_spacing(
             &self,
-            token_a: ::ethers::core::types::Address,
-            token_b: ::ethers::core::types::Address,
-        ) -> ::ethers::contract::builders::ContractCall<
-            M,
-            ::ethers::core::types::Address,
-        > {
+            p0: u32,
+        ) -> ::ethers::contract::builders::ContractCall<M, i32> {
             self.0
-                .method_hash([201, 198, 83, 150], (token_a, token_b))
+                .method_hash([34, 175, 204, 203], p0)
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `feeTo` (0x017e7e58) function
-        pub fn fee_to(
+        ///Calls the contract's `getPool` (0x1698ee82) function
+        pub fn get_pool(
             &self,
+            p0: ::ethers::core::types::Address,
+            p1: ::ethers::core::types::Address,
+            p2: u32,
         ) -> ::ethers::contract::builders::ContractCall<
             M,
             ::ethers::core::types::Address,
         > {
             self.0
-                .method_hash([1, 126, 126, 88], ())
+                .method_hash([22, 152, 238, 130], (p0, p1, p2))
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `feeToSetter` (0x094b7415) function
-        pub fn fee_to_setter(
+        ///Calls the contract's `owner` (0x8da5cb5b) function
+        pub fn owner(
             &self,
         ) -> ::ethers::contract::builders::ContractCall<
             M,
             ::ethers::core::types::Address,
         > {
             self.0
-                .method_hash([9, 75, 116, 21], ())
+                .method_hash([141, 165, 203, 91], ())
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `getPair` (0xe6a43905) function
-        pub fn get_pair(
+        ///Calls the contract's `parameters` (0x89035730) function
+        pub fn parameters(
             &self,
-            p0: ::ethers::core::types::Address,
-            p1: ::ethers::core::types::Address,
         ) -> ::ethers::contract::builders::ContractCall<
             M,
-            ::ethers::core::types::Address,
+            (
+                ::ethers::core::types::Address,
+                ::ethers::core::types::Address,
+                ::ethers::core::types::Address,
+                u32,
+                i32,
+            ),
         > {
             self.0
-                .method_hash([230, 164, 57, 5], (p0, p1))
+                .method_hash([137, 3, 87, 48], ())
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `setFeeTo` (0xf46901ed) function
-        pub fn set_fee_to(
+        ///Calls the contract's `setOwner` (0x13af4035) function
+        pub fn set_owner(
             &self,
-            fee_to: ::ethers::core::types::Address,
+            owner: ::ethers::core::types::Address,
         ) -> ::ethers::contract::builders::ContractCall<M, ()> {
             self.0
-                .method_hash([244, 105, 1, 237], fee_to)
+                .method_hash([19, 175, 64, 53], owner)
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `setFeeToSetter` (0xa2e74af6) function
-        pub fn set_fee_to_setter(
+        ///Gets the contract's `FeeAmountEnabled` event
+        pub fn fee_amount_enabled_filter(
             &self,
-            fee_to_setter: ::ethers::core::types::Address,
-        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
-            self.0
-                .method_hash([162, 231, 74, 246], fee_to_setter)
-                .expect("method not found (this should never happen)")
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            FeeAmountEnabledFilter,
+        > {
+            self.0.event()
+        }
+        ///Gets the contract's `OwnerChanged` event
+        pub fn owner_changed_filter(
+            &self,
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            OwnerChangedFilter,
+        > {
+            self.0.event()
         }
-        ///Gets the contract's `PairCreated` event
-        pub fn pair_created_filter(
+        ///Gets the contract's `PoolCreated` event
+        pub fn pool_created_filter(
             &self,
         ) -> ::ethers::contract::builders::Event<
             ::std::sync::Arc<M>,
             M,
-            PairCreatedFilter,
+            PoolCreatedFilter,
         > {
             self.0.event()
         }
@@ -413,7 +495,7 @@ pub mod uniswap_v3_factory {
         ) -> ::ethers::contract::builders::Event<
             ::std::sync::Arc<M>,
             M,
-            PairCreatedFilter,
+            UNISWAP_V3_FACTORYEvents,
         > {
             self.0.event_with_filter(::core::default::Default::default())
         }
@@ -434,22 +516,16 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethevent(
-        name = "PairCreated",
-        abi = "PairCreated(address,address,address,uint256)"
-    )]
-    pub struct PairCreatedFilter {
+    #[ethevent(name = "FeeAmountEnabled", abi = "FeeAmountEnabled(uint24,int24)")]
+    pub struct FeeAmountEnabledFilter {
         #[ethevent(indexed)]
-        pub token_0: ::ethers::core::types::Address,
+        pub fee: u32,
         #[ethevent(indexed)]
-        pub token_1: ::ethers::core::types::Address,
-        pub pair: ::ethers::core::types::Address,
-        pub p3: ::ethers::core::types::U256,
+        pub tick_spacing: i32,
     }
-    ///Container type for all input parameters for the `allPairs` function with signature `allPairs(uint256)` and selector `0x1e3dd18b`
     #[derive(
         Clone,
-        ::ethers::contract::EthCall,
+        ::ethers::contract::EthEvent,
         ::ethers::contract::EthDisplay,
         Default,
         Debug,
@@ -457,12 +533,16 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "allPairs", abi = "allPairs(uint256)")]
-    pub struct AllPairsCall(pub ::ethers::core::types::U256);
-    ///Container type for all input parameters for the `allPairsLength` function with signature `allPairsLength()` and selector `0x574f2ba3`
+    #[ethevent(name = "OwnerChanged", abi = "OwnerChanged(address,address)")]
+    pub struct OwnerChangedFilter {
+        #[ethevent(indexed)]
+        pub old_owner: ::ethers::core::types::Address,
+        #[ethevent(indexed)]
+        pub new_owner: ::ethers::core::types::Address,
+    }
     #[derive(
         Clone,
-        ::ethers::contract::EthCall,
+        ::ethers::contract::EthEvent,
         ::ethers::contract::EthDisplay,
         Default,
         Debug,
@@ -470,9 +550,72 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "allPairsLength", abi = "allPairsLength()")]
-    pub struct AllPairsLengthCall;
-    ///Container type for all input parameters for the `createPair` function with signature `createPair(address,address)` and selector `0xc9c65396`
+    #[ethevent(
+        name = "PoolCreated",
+        abi = "PoolCreated(address,address,uint24,int24,address)"
+    )]
+    pub struct PoolCreatedFilter {
+        #[ethevent(indexed)]
+        pub token_0: ::ethers::core::types::Address,
+        #[ethevent(indexed)]
+        pub token_1: ::ethers::core::types::Address,
+        #[ethevent(indexed)]
+        pub fee: u32,
+        pub tick_spacing: i32,
+        pub pool: ::ethers::core::types::Address,
+    }
+    ///Container type for all of the contract's events
+    #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
+    pub enum UNISWAP_V3_FACTORYEvents {
+        FeeAmountEnabledFilter(FeeAmountEnabledFilter),
+        OwnerChangedFilter(OwnerChangedFilter),
+        PoolCreatedFilter(PoolCreatedFilter),
+    }
+    impl ::ethers::contract::EthLogDecode for UNISWAP_V3_FACTORYEvents {
+        fn decode_log(
+            log: &::ethers::core::abi::RawLog,
+        ) -> ::core::result::Result<Self, ::ethers::core::abi::Error> {
+            if let Ok(decoded) = FeeAmountEnabledFilter::decode_log(log) {
+                return Ok(UNISWAP_V3_FACTORYEvents::FeeAmountEnabledFilter(decoded));
+            }
+            if let Ok(decoded) = OwnerChangedFilter::decode_log(log) {
+                return Ok(UNISWAP_V3_FACTORYEvents::OwnerChangedFilter(decoded));
+            }
+            if let Ok(decoded) = PoolCreatedFilter::decode_log(log) {
+                return Ok(UNISWAP_V3_FACTORYEvents::PoolCreatedFilter(decoded));
+            }
+            Err(::ethers::core::abi::Error::InvalidData)
+        }
+    }
+    impl ::core::fmt::Display for UNISWAP_V3_FACTORYEvents {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            match self {
+                Self::FeeAmountEnabledFilter(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+                Self::OwnerChangedFilter(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+                Self::PoolCreatedFilter(element) => ::core::fmt::Display::fmt(element, f),
+            }
+        }
+    }
+    impl ::core::convert::From<FeeAmountEnabledFilter> for UNISWAP_V3_FACTORYEvents {
+        fn from(value: FeeAmountEnabledFilter) -> Self {
+            Self::FeeAmountEnabledFilter(value)
+        }
+    }
+    impl ::core::convert::From<OwnerChangedFilter> for UNISWAP_V3_FACTORYEvents {
+        fn from(value: OwnerChangedFilter) -> Self {
+            Self::OwnerChangedFilter(value)
+        }
+    }
+    impl ::core::convert::From<PoolCreatedFilter> for UNISWAP_V3_FACTORYEvents {
+        fn from(value: PoolCreatedFilter) -> Self {
+            Self::PoolCreatedFilter(value)
+        }
+    }
+    ///Container type for all input parameters for the `createPool` function with signature `createPool(address,address,uint24)` and selector `0xa1671295`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -483,12 +626,13 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "createPair", abi = "createPair(address,address)")]
-    pub struct CreatePairCall {
+    #[ethcall(name = "createPool", abi = "createPool(address,address,uint24)")]
+    pub struct CreatePoolCall {
         pub token_a: ::ethers::core::types::Address,
         pub token_b: ::ethers::core::types::Address,
+        pub fee: u32,
     }
-    ///Container type for all input parameters for the `feeTo` function with signature `feeTo()` and selector `0x017e7e58`
+    ///Container type for all input parameters for the `enableFeeAmount` function with signature `enableFeeAmount(uint24,int24)` and selector `0x8a7c195f`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -499,9 +643,12 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "feeTo", abi = "feeTo()")]
-    pub struct FeeToCall;
-    ///Container type for all input parameters for the `feeToSetter` function with signature `feeToSetter()` and selector `0x094b7415`
+    #[ethcall(name = "enableFeeAmount", abi = "enableFeeAmount(uint24,int24)")]
+    pub struct EnableFeeAmountCall {
+        pub fee: u32,
+        pub tick_spacing: i32,
+    }
+    ///Container type for all input parameters for the `feeAmountTickSpacing` function with signature `feeAmountTickSpacing(uint24)` and selector `0x22afcccb`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -512,9 +659,9 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "feeToSetter", abi = "feeToSetter()")]
-    pub struct FeeToSetterCall;
-    ///Container type for all input parameters for the `getPair` function with signature `getPair(address,address)` and selector `0xe6a43905`
+    #[ethcall(name = "feeAmountTickSpacing", abi = "feeAmountTickSpacing(uint24)")]
+    pub struct FeeAmountTickSpacingCall(pub u32);
+    ///Container type for all input parameters for the `getPool` function with signature `getPool(address,address,uint24)` and selector `0x1698ee82`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -525,12 +672,13 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "getPair", abi = "getPair(address,address)")]
-    pub struct GetPairCall(
+    #[ethcall(name = "getPool", abi = "getPool(address,address,uint24)")]
+    pub struct GetPoolCall(
         pub ::ethers::core::types::Address,
         pub ::ethers::core::types::Address,
+        pub u32,
     );
-    ///Container type for all input parameters for the `setFeeTo` function with signature `setFeeTo(address)` and selector `0xf46901ed`
+    ///Container type for all input parameters for the `owner` function with signature `owner()` and selector `0x8da5cb5b`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -541,11 +689,9 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "setFeeTo", abi = "setFeeTo(address)")]
-    pub struct SetFeeToCall {
-        pub fee_to: ::ethers::core::types::Address,
-    }
-    ///Container type for all input parameters for the `setFeeToSetter` function with signature `setFeeToSetter(address)` and selector `0xa2e74af6`
+    #[ethcall(name = "owner", abi = "owner()")]
+    pub struct OwnerCall;
+    ///Container type for all input parameters for the `parameters` function with signature `parameters()` and selector `0x89035730`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -556,66 +702,73 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    #[ethcall(name = "setFeeToSetter", abi = "setFeeToSetter(address)")]
-    pub struct SetFeeToSetterCall {
-        pub fee_to_setter: ::ethers::core::types::Address,
+    #[ethcall(name = "parameters", abi = "parameters()")]
+    pub struct ParametersCall;
+    ///Container type for all input parameters for the `setOwner` function with signature `setOwner(address)` and selector `0x13af4035`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "setOwner", abi = "setOwner(address)")]
+    pub struct SetOwnerCall {
+        pub owner: ::ethers::core::types::Address,
     }
     ///Container type for all of the contract's call
     #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
     pub enum UNISWAP_V3_FACTORYCalls {
-        AllPairs(AllPairsCall),
-        AllPairsLength(AllPairsLengthCall),
-        CreatePair(CreatePairCall),
-        FeeTo(FeeToCall),
-        FeeToSetter(FeeToSetterCall),
-        GetPair(GetPairCall),
-        SetFeeTo(SetFeeToCall),
-        SetFeeToSetter(SetFeeToSetterCall),
+        CreatePool(CreatePoolCall),
+        EnableFeeAmount(EnableFeeAmountCall),
+        FeeAmountTickSpacing(FeeAmountTickSpacingCall),
+        GetPool(GetPoolCall),
+        Owner(OwnerCall),
+        Parameters(ParametersCall),
+        SetOwner(SetOwnerCall),
     }
     impl ::ethers::core::abi::AbiDecode for UNISWAP_V3_FACTORYCalls {
         fn decode(
             data: impl AsRef<[u8]>,
         ) -> ::core::result::Result<Self, ::ethers::core::abi::AbiError> {
             let data = data.as_ref();
-            if let Ok(decoded) = <AllPairsCall as ::ethers::core::abi::AbiDecode>::decode(
-                data,
-            ) {
-                return Ok(Self::AllPairs(decoded));
-            }
-            if let Ok(decoded) = <AllPairsLengthCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <CreatePoolCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::AllPairsLength(decoded));
+                return Ok(Self::CreatePool(decoded));
             }
-            if let Ok(decoded) = <CreatePairCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <EnableFeeAmountCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::CreatePair(decoded));
+                return Ok(Self::EnableFeeAmount(decoded));
             }
-            if let Ok(decoded) = <FeeToCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <FeeAmountTickSpacingCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::FeeTo(decoded));
+                return Ok(Self::FeeAmountTickSpacing(decoded));
             }
-            if let Ok(decoded) = <FeeToSetterCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <GetPoolCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::FeeToSetter(decoded));
+                return Ok(Self::GetPool(decoded));
             }
-            if let Ok(decoded) = <GetPairCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <OwnerCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::GetPair(decoded));
+                return Ok(Self::Owner(decoded));
             }
-            if let Ok(decoded) = <SetFeeToCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <ParametersCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::SetFeeTo(decoded));
+                return Ok(Self::Parameters(decoded));
             }
-            if let Ok(decoded) = <SetFeeToSetterCall as ::ethers::core::abi::AbiDecode>::decode(
+            if let Ok(decoded) = <SetOwnerCall as ::ethers::core::abi::AbiDecode>::decode(
                 data,
             ) {
-                return Ok(Self::SetFeeToSetter(decoded));
+                return Ok(Self::SetOwner(decoded));
             }
             Err(::ethers::core::abi::Error::InvalidData.into())
         }
@@ -623,24 +776,21 @@ pub mod uniswap_v3_factory {
     impl ::ethers::core::abi::AbiEncode for UNISWAP_V3_FACTORYCalls {
         fn encode(self) -> Vec<u8> {
             match self {
-                Self::AllPairs(element) => {
+                Self::CreatePool(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
-                Self::AllPairsLength(element) => {
+                Self::EnableFeeAmount(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
-                Self::CreatePair(element) => {
+                Self::FeeAmountTickSpacing(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
-                Self::FeeTo(element) => ::ethers::core::abi::AbiEncode::encode(element),
-                Self::FeeToSetter(element) => {
+                Self::GetPool(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Owner(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Parameters(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
-                Self::GetPair(element) => ::ethers::core::abi::AbiEncode::encode(element),
-                Self::SetFeeTo(element) => {
-                    ::ethers::core::abi::AbiEncode::encode(element)
-                }
-                Self::SetFeeToSetter(element) => {
+                Self::SetOwner(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
             }
@@ -649,58 +799,54 @@ pub mod uniswap_v3_factory {
     impl ::core::fmt::Display for UNISWAP_V3_FACTORYCalls {
         fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
             match self {
-                Self::AllPairs(element) => ::core::fmt::Display::fmt(element, f),
-                Self::AllPairsLength(element) => ::core::fmt::Display::fmt(element, f),
-                Self::CreatePair(element) => ::core::fmt::Display::fmt(element, f),
-                Self::FeeTo(element) => ::core::fmt::Display::fmt(element, f),
-                Self::FeeToSetter(element) => ::core::fmt::Display::fmt(element, f),
-                Self::GetPair(element) => ::core::fmt::Display::fmt(element, f),
-                Self::SetFeeTo(element) => ::core::fmt::Display::fmt(element, f),
-                Self::SetFeeToSetter(element) => ::core::fmt::Display::fmt(element, f),
+                Self::CreatePool(element) => ::core::fmt::Display::fmt(element, f),
+                Self::EnableFeeAmount(element) => ::core::fmt::Display::fmt(element, f),
+                Self::FeeAmountTickSpacing(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+                Self::GetPool(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Owner(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Parameters(element) => ::core::fmt::Display::fmt(element, f),
+                Self::SetOwner(element) => ::core::fmt::Display::fmt(element, f),
             }
         }
     }
-    impl ::core::convert::From<AllPairsCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: AllPairsCall) -> Self {
-            Self::AllPairs(value)
+    impl ::core::convert::From<CreatePoolCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: CreatePoolCall) -> Self {
+            Self::CreatePool(value)
         }
     }
-    impl ::core::convert::From<AllPairsLengthCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: AllPairsLengthCall) -> Self {
-            Self::AllPairsLength(value)
+    impl ::core::convert::From<EnableFeeAmountCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: EnableFeeAmountCall) -> Self {
+            Self::EnableFeeAmount(value)
         }
     }
-    impl ::core::convert::From<CreatePairCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: CreatePairCall) -> Self {
-            Self::CreatePair(value)
+    impl ::core::convert::From<FeeAmountTickSpacingCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: FeeAmountTickSpacingCall) -> Self {
+            Self::FeeAmountTickSpacing(value)
         }
     }
-    impl ::core::convert::From<FeeToCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: FeeToCall) -> Self {
-            Self::FeeTo(value)
+    impl ::core::convert::From<GetPoolCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: GetPoolCall) -> Self {
+            Self::GetPool(value)
         }
     }
-    impl ::core::convert::From<FeeToSetterCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: FeeToSetterCall) -> Self {
-            Self::FeeToSetter(value)
+    impl ::core::convert::From<OwnerCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: OwnerCall) -> Self {
+            Self::Owner(value)
         }
     }
-    impl ::core::convert::From<GetPairCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: GetPairCall) -> Self {
-            Self::GetPair(value)
+    impl ::core::convert::From<ParametersCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: ParametersCall) -> Self {
+            Self::Parameters(value)
         }
     }
-    impl ::core::convert::From<SetFeeToCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: SetFeeToCall) -> Self {
-            Self::SetFeeTo(value)
+    impl ::core::convert::From<SetOwnerCall> for UNISWAP_V3_FACTORYCalls {
+        fn from(value: SetOwnerCall) -> Self {
+            Self::SetOwner(value)
         }
     }
-    impl ::core::convert::From<SetFeeToSetterCall> for UNISWAP_V3_FACTORYCalls {
-        fn from(value: SetFeeToSetterCall) -> Self {
-            Self::SetFeeToSetter(value)
-        }
-    }
-    ///Container type for all return fields from the `allPairs` function with signature `allPairs(uint256)` and selector `0x1e3dd18b`
+    ///Container type for all return fields from the `createPool` function with signature `createPool(address,address,uint24)` and selector `0xa1671295`
     #[derive(
         Clone,
         ::ethers::contract::EthAbiType,
@@ -711,20 +857,10 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    pub struct AllPairsReturn(pub ::ethers::core::types::Address);
-    ///Container type for all return fields from the `allPairsLength` function with signature `allPairsLength()` and selector `0x574f2ba3`
-    #[derive(
-        Clone,
-        ::ethers::contract::EthAbiType,
-        ::ethers::contract::EthAbiCodec,
-        Default,
-        Debug,
-        PartialEq,
-        Eq,
-        Hash
-    )]
-    pub struct AllPairsLengthReturn(pub ::ethers::core::types::U256);
-    ///Container type for all return fields from the `createPair` function with signature `createPair(address,address)` and selector `0xc9c65396`
+    pub struct CreatePoolReturn {
+        pub pool: ::ethers::core::types::Address,
+    }
+    ///Container type for all return fields from the `feeAmountTickSpacing` function with signature `feeAmountTickSpacing(uint24)` and selector `0x22afcccb`
     #[derive(
         Clone,
         ::ethers::contract::EthAbiType,
@@ -735,10 +871,8 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    pub struct CreatePairReturn {
-        pub pair: ::ethers::core::types::Address,
-    }
-    ///Container type for all return fields from the `feeTo` function with signature `feeTo()` and selector `0x017e7e58`
+    pub struct FeeAmountTickSpacingReturn(pub i32);
+    ///Container type for all return fields from the `getPool` function with signature `getPool(address,address,uint24)` and selector `0x1698ee82`
     #[derive(
         Clone,
         ::ethers::contract::EthAbiType,
@@ -749,8 +883,8 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    pub struct FeeToReturn(pub ::ethers::core::types::Address);
-    ///Container type for all return fields from the `feeToSetter` function with signature `feeToSetter()` and selector `0x094b7415`
+    pub struct GetPoolReturn(pub ::ethers::core::types::Address);
+    ///Container type for all return fields from the `owner` function with signature `owner()` and selector `0x8da5cb5b`
     #[derive(
         Clone,
         ::ethers::contract::EthAbiType,
@@ -761,8 +895,8 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    pub struct FeeToSetterReturn(pub ::ethers::core::types::Address);
-    ///Container type for all return fields from the `getPair` function with signature `getPair(address,address)` and selector `0xe6a43905`
+    pub struct OwnerReturn(pub ::ethers::core::types::Address);
+    ///Container type for all return fields from the `parameters` function with signature `parameters()` and selector `0x89035730`
     #[derive(
         Clone,
         ::ethers::contract::EthAbiType,
@@ -773,5 +907,11 @@ pub mod uniswap_v3_factory {
         Eq,
         Hash
     )]
-    pub struct GetPairReturn(pub ::ethers::core::types::Address);
+    pub struct ParametersReturn {
+        pub factory: ::ethers::core::types::Address,
+        pub token_0: ::ethers::core::types::Address,
+        pub token_1: ::ethers::core::types::Address,
+        pub fee: u32,
+        pub tick_spacing: i32,
+    }
 }
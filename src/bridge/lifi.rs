@@ -1,9 +1,15 @@
 use reqwest::Client;
 use std::error::Error;
+use std::fs;
 
 const LIFI_API_URL: &str = "https://li.quest/v1";
 
+/// How long a cached token list is used before it is fetched again.
+const TOKEN_LIST_TTL_SECS: u64 = 24 * 3600;
+
 use crate::bridge::lifi_types::*;
+use crate::config::get_token_list_cache_path;
+use crate::utils;
 
 pub struct LiFiBridge {
     client: Client,
@@ -32,6 +38,37 @@ impl LiFiBridge {
         Ok(json.tokens.get(chain).unwrap_or(&vec![]).to_vec())
     }
 
+    /// Known tokens of `chain_id`, read from the local cache when it was
+    /// fetched less than a day ago.
+    pub async fn get_known_tokens_cached(
+        &self,
+        chain_id: u64,
+    ) -> Result<Vec<LifiToken>, Box<dyn Error>> {
+        let path = get_token_list_cache_path();
+        let mut cache: TokenListCache = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let key = chain_id.to_string();
+        let now = utils::unix_now();
+        if let Some(cached) = cache.chains.get(&key) {
+            if now.saturating_sub(cached.fetched_at) < TOKEN_LIST_TTL_SECS {
+                return Ok(cached.tokens.clone());
+            }
+        }
+
+        let tokens = self.get_known_tokens(&key).await?;
+        cache.chains.insert(
+            key,
+            CachedTokenList {
+                fetched_at: now,
+                tokens: tokens.clone(),
+            },
+        );
+        fs::write(&path, serde_json::to_string(&cache)?)?;
+        Ok(tokens)
+    }
+
     pub async fn request_routes(
         &self,
         request: RouteRequest,
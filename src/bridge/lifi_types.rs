@@ -14,6 +14,18 @@ pub struct LifiTokenListResponse {
     pub tokens: HashMap<String, Vec<LifiToken>>,
 }
 
+/// Token lists fetched from LI.FI, keyed by chain id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenListCache {
+    pub chains: HashMap<String, CachedTokenList>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedTokenList {
+    pub fetched_at: u64,
+    pub tokens: Vec<LifiToken>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LifiConnectionResponse {
@@ -149,6 +161,7 @@ pub struct Action {
     pub to_token: LifiToken,
     pub from_amount: String,
     pub slippage: f64,
+    pub to_address: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -158,7 +171,9 @@ pub struct Estimate {
     pub to_amount: String,
     pub to_amount_min: String,
     pub approval_address: String,
+    #[serde(default)]
     pub fee_costs: Vec<FeeCost>,
+    #[serde(default)]
     pub gas_costs: Vec<GasCost>,
     pub data: Option<serde_json::Value>,
 }
@@ -206,6 +221,8 @@ pub struct QuoteRequest {
     from_amount: String,
     from_address: String,
     to_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slippage: Option<f64>,
 }
 
 impl QuoteRequest {
@@ -226,14 +243,35 @@ impl QuoteRequest {
             from_amount,
             from_address,
             to_address,
+            slippage: None,
         }
     }
+
+    /// Maximum slippage as a fraction, LI.FI defaults to 0.005.
+    pub fn with_slippage(mut self, slippage: f64) -> Self {
+        self.slippage = Some(slippage);
+        self
+    }
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Quote {
     id: String,
-    // Add other fields as needed
+    pub tool: String,
+    pub action: Action,
+    pub estimate: Estimate,
+    pub transaction_request: Option<LifiTransactionRequest>,
+}
+
+/// Transaction to send to execute a quote. Numbers are hex encoded.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifiTransactionRequest {
+    pub to: String,
+    pub data: String,
+    pub value: String,
+    pub gas_limit: Option<String>,
 }
 
 // Add this new struct to the existing ones
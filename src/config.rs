@@ -37,10 +37,30 @@ pub static CHAIN_MAP: Lazy<HashMap<String, Chain>> = Lazy::new(|| {
 
 pub const DEFAULT_WHITELIST_PATH: &str = "whitelist.json";
 
+pub const DEFAULT_JOURNAL_PATH: &str = "journal.jsonl";
+
+pub const DEFAULT_ADDRESSBOOK_PATH: &str = "addressbook.json";
+
+pub const DEFAULT_TOKEN_LIST_CACHE_PATH: &str = "lifi_tokens.json";
+
+pub const ALL_CHAINS: &str = "all";
+
 pub fn get_whitelist_path() -> PathBuf {
     PathBuf::from(DEFAULT_WHITELIST_PATH)
 }
 
+pub fn get_journal_path() -> PathBuf {
+    PathBuf::from(DEFAULT_JOURNAL_PATH)
+}
+
+pub fn get_addressbook_path() -> PathBuf {
+    PathBuf::from(DEFAULT_ADDRESSBOOK_PATH)
+}
+
+pub fn get_token_list_cache_path() -> PathBuf {
+    PathBuf::from(DEFAULT_TOKEN_LIST_CACHE_PATH)
+}
+
 pub async fn get_chain_config(chain: Chain) -> ChainConfig {
     match chain {
         Chain::Mainnet => {
@@ -95,9 +115,25 @@ pub async fn get_chain_config(chain: Chain) -> ChainConfig {
     }
 }
 
+/// Provider used for ENS lookups. ENS lives on mainnet, so names are resolved
+/// there whatever network a command targets.
+pub fn get_ens_provider() -> Option<Provider<Http>> {
+    let url = env::var("MAINNET_RPC_URL").ok()?;
+    Provider::<Http>::try_from(url).ok()
+}
+
+/// Returns true if `get_chain_config` can build a configuration for `chain`.
+pub fn is_chain_supported(chain: Chain) -> bool {
+    matches!(chain, Chain::Mainnet | Chain::Arbitrum | Chain::Optimism)
+}
+
 // Add this new function to get Chain from string
 pub fn get_chain_from_string(chain_name: &str) -> Option<Chain> {
     println!("Chain name: {}", chain_name);
+    // Numeric chain ids are accepted wherever a network name is
+    if let Ok(chain_id) = chain_name.parse::<u64>() {
+        return Chain::try_from(chain_id).ok();
+    }
     CHAIN_MAP.get(chain_name).cloned()
 }
 
@@ -108,3 +144,14 @@ pub fn get_chain_id_from_string(chain_name: &str) -> Option<u64> {
         None => None,
     }
 }
+
+/// Parses a chain argument that may also be "all", returning `None` when the
+/// entry should apply to every chain.
+pub fn get_chain_scope_from_string(chain_name: &str) -> Result<Option<u64>, String> {
+    if chain_name == ALL_CHAINS {
+        return Ok(None);
+    }
+    get_chain_id_from_string(chain_name)
+        .map(Some)
+        .ok_or_else(|| format!("Unsupported network: {}", chain_name))
+}
@@ -1,27 +1,72 @@
-use ethers::abi::{Abi, Token};
+use ethers::abi::{Abi, Detokenize};
+use ethers::etherscan::account::{Sort, TokenQueryOption, TxListParams};
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::types::{Address, Filter, H256, U64};
-use openzeppelin_rs::ERC20;
+use openzeppelin_rs::{IERC20Permit, ERC20};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::approval::{self, ApprovalMode};
 use crate::bindings::uniswap_v2_factory::UNISWAP_V2_FACTORY;
-use crate::bindings::uniswap_v2_pool::UNISWAP_V2_POOL;
+use crate::bindings::uniswap_v2_pool::{
+    MintFilter as V2MintFilter, TransferFilter as V2TransferFilter, UNISWAP_V2_POOL,
+};
 use crate::bindings::uniswap_v2_router::UNISWAP_V2_ROUTER;
-use crate::bindings::uniswap_v3_router::{ExactInputParams, UNISWAP_V3_ROUTER};
+use crate::bindings::uniswap_v3_factory::UNISWAP_V3_FACTORY;
+use crate::bindings::uniswap_v3_pool::UNISWAP_V3_POOL;
+use crate::bindings::uniswap_v3_position_manager::{
+    CollectParams, DecreaseLiquidityFilter, DecreaseLiquidityParams, IncreaseLiquidityFilter,
+    IncreaseLiquidityParams, MintParams, TransferFilter, UNISWAP_V3_POSITION_MANAGER,
+};
+use crate::bindings::uniswap_v3_quoter_v2::UNISWAP_V3_QUOTER_V2;
+use crate::bindings::uniswap_v3_router::{ExactInputParams, ExactOutputParams, UNISWAP_V3_ROUTER};
+use crate::bindings::usdt::usdt::usdt as USDT;
 use crate::bindings::weth::WETH;
+use crate::bridge::lifi::LiFiBridge;
+use crate::bridge::lifi_types::{Quote as LifiQuote, QuoteRequest};
 use crate::config::{get_chain_config, get_chain_from_string, ChainConfig};
-use crate::whitelist::Whitelist;
-use crate::{addressbook, utils};
+use crate::journal::{self, JournalEntry};
+use crate::lookalike::{self, SuspiciousTransfer};
+use crate::resolver::Resolver;
+use crate::uniswap::{
+    self, LiquidityShare, PoolState, Position, PriceRange, Quote, Route, SwapKind, SwapPath,
+    TwapGuard,
+};
+use crate::whitelist::{TokenType, Whitelist};
+use crate::{addressbook, policy, tick_math, utils};
+
+type HttpSigner = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Block range of each `eth_getLogs` query when a node refuses to scan the
+/// whole chain at once.
+const LOG_CHUNK_BLOCKS: u64 = 50_000;
+
+/// Where a `best-swap` candidate is executed.
+enum SwapVenue {
+    UniswapV2(Vec<Address>),
+    UniswapV3(SwapPath),
+    LiFi(Box<LifiQuote>),
+}
+
+struct SwapCandidate {
+    label: String,
+    venue: SwapVenue,
+    amount_out: U256,
+    /// Gas cost in wei of the native asset.
+    gas_cost: U256,
+}
 
 pub struct EVMInterface {
     config: Arc<ChainConfig>,
     explorer_client: Arc<Client>,
     network: String,
     whitelist: Arc<Whitelist>,
+    approval_mode: ApprovalMode,
 }
 
 impl EVMInterface {
@@ -39,9 +84,25 @@ impl EVMInterface {
             network: network.to_string(),
             explorer_client: Arc::new(explorer_client),
             whitelist,
+            approval_mode: ApprovalMode::default(),
         })
     }
 
+    pub fn with_approval_mode(mut self, approval_mode: ApprovalMode) -> Self {
+        self.approval_mode = approval_mode;
+        self
+    }
+
+    /// Address of the wallet signing transactions.
+    pub fn wallet_address(&self) -> Address {
+        self.config.http.address()
+    }
+
+    /// Chain the interface was built for.
+    pub fn chain(&self) -> Chain {
+        self.config.chain
+    }
+
     pub async fn get_block_number(&self) -> Result<(), Box<dyn std::error::Error>> {
         let block_number: U64 = self.config.http.get_block_number().await?;
         println!("Current block number on {}: {block_number}", self.network);
@@ -136,14 +197,21 @@ impl EVMInterface {
         Ok(())
     }
 
-    pub async fn get_tx_details(&self, tx_hash: String) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn get_tx_details(
+        &self,
+        tx_hash: String,
+        resolver: &Resolver,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let tx_hash = H256::from_str(&tx_hash)?;
         let tx = self.config.http.get_transaction(tx_hash).await?;
 
         match tx {
             Some(tx) => {
+                let names = resolver
+                    .lookup_names(std::iter::once(tx.from).chain(tx.to), self.config.chain)
+                    .await;
                 println!("Transaction details for {:?} on {}:", tx_hash, self.network);
-                utils::print_tx_details(&tx);
+                utils::print_tx_details(&tx, &names);
             }
             None => println!("Transaction {:?} not found on {}", tx_hash, self.network),
         }
@@ -277,19 +345,14 @@ impl EVMInterface {
 
     pub async fn send_eth(
         &self,
-        to_address: String,
+        to_address: Address,
         amount: u64,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let to_address = Address::from_str(&to_address)?;
-
-        if !self
-            .whitelist
-            .is_wallet_whitelisted(&to_address.to_string())
-        {
-            return Err("Recipient address is not whitelisted".into());
-        }
+        self.ensure_recipient_whitelisted(to_address)?;
+        self.ensure_recipient_not_poisoned(to_address).await?;
 
         let amount = U256::from(amount);
+        self.enforce_spending_policy(None, to_address, amount)?;
 
         let tx = TransactionRequest::new()
             .to(to_address)
@@ -298,6 +361,7 @@ impl EVMInterface {
 
         let pending_tx = self.config.http.send_transaction(tx, None).await?;
         println!("Transaction sent: {:?}", pending_tx.tx_hash());
+        self.record_spend("send_eth", pending_tx.tx_hash(), None, to_address, amount)?;
 
         let receipt = pending_tx.await?;
         println!("Transaction receipt: {:?}", receipt);
@@ -307,50 +371,480 @@ impl EVMInterface {
 
     pub async fn send_erc20(
         &self,
-        token_address: String,
-        to_address: String,
+        token_address: Address,
+        to_address: Address,
         amount: u64,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let token_address = Address::from_str(&token_address)?;
-        let to_address = Address::from_str(&to_address)?;
+        self.ensure_token_whitelisted(token_address, "Token")?;
+
+        self.ensure_recipient_whitelisted(to_address)?;
+        self.ensure_recipient_not_poisoned(to_address).await?;
+
+        let amount = U256::from(amount);
+        self.enforce_spending_policy(Some(token_address), to_address, amount)?;
+
+        let token = ERC20::new(token_address, self.config.http.clone());
+        let tx = token.transfer(to_address, amount);
+
+        let pending_tx = tx.send().await?;
+        println!("Transaction sent: {:?}", pending_tx.tx_hash());
+        self.record_spend(
+            "send_erc20",
+            pending_tx.tx_hash(),
+            Some(token_address),
+            to_address,
+            amount,
+        )?;
+
+        let receipt = pending_tx.await?;
+        println!("Transaction receipt: {:?}", receipt);
+
+        Ok(())
+    }
+
+    fn ensure_recipient_whitelisted(
+        &self,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(reason) = self.whitelist.read_only_reason() {
+            return Err(
+                format!("Whitelist cannot be trusted, refusing to send: {}", reason).into(),
+            );
+        }
 
         if !self
             .whitelist
-            .is_token_whitelisted(&token_address.to_string(), self.config.chain_id)
+            .is_wallet_whitelisted(recipient, self.config.chain_id)
         {
-            return Err("Token address is not whitelisted".into());
+            return Err(match self
+                .whitelist
+                .wallet_active_from(recipient, self.config.chain_id)
+            {
+                Some(active_from) => format!(
+                    "Recipient address {:?} is pending whitelist activation for another {}",
+                    recipient,
+                    utils::format_duration(active_from.saturating_sub(utils::unix_now()))
+                ),
+                None => {
+                    let lookalikes = lookalike::find_lookalikes(
+                        &self.whitelist,
+                        Some(self.config.chain_id),
+                        recipient,
+                    );
+                    if lookalikes.is_empty() {
+                        format!(
+                            "Recipient address {:?} is not whitelisted on {}",
+                            recipient, self.network
+                        )
+                    } else {
+                        format!(
+                            "Recipient address {:?} is not whitelisted on {} but looks like:\n  {}\nThis is likely a poisoned address, check where it was copied from",
+                            recipient,
+                            self.network,
+                            lookalike::describe(&lookalikes)
+                        )
+                    }
+                }
+            }
+            .into());
         }
 
-        if !self
+        // Two whitelisted wallets that only differ in the middle may mean a
+        // poisoned address made it into the whitelist.
+        let lookalikes =
+            lookalike::find_lookalikes(&self.whitelist, Some(self.config.chain_id), recipient);
+        if !lookalikes.is_empty() {
+            println!(
+                "Warning: recipient {:?} looks like other whitelisted wallets:\n  {}",
+                recipient,
+                lookalike::describe(&lookalikes)
+            );
+            if !utils::confirm("Is this the intended recipient?")? {
+                return Err("Send cancelled, recipient looks like another wallet".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuses to send to an address that took part in a zero-value transfer
+    /// mimicking one of the sender's counterparties. Explorer failures only
+    /// produce a warning, since not every chain has an API key configured.
+    async fn ensure_recipient_not_poisoned(
+        &self,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let transfers = match self
+            .find_suspicious_transfers(self.config.http.address())
+            .await
+        {
+            Ok(transfers) => transfers,
+            Err(e) => {
+                println!(
+                    "Warning: could not scan recent transfers for address poisoning: {}",
+                    e
+                );
+                return Ok(());
+            }
+        };
+        if let Some(transfer) = transfers.iter().find(|t| t.counterparty == recipient) {
+            return Err(format!(
+                "Recipient {:?} sent or received a zero-value transfer ({:?}) with this wallet and mimics {:?} ({}), refusing to send to a likely poisoned address",
+                recipient, transfer.tx_hash, transfer.mimics, transfer.mimics_label
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Scans recent token transfers of `wallet` for zero-value transfers whose
+    /// counterparty is a lookalike of a whitelisted wallet, of the wallet
+    /// itself, or of an address the wallet has sent tokens to.
+    pub async fn find_suspicious_transfers(
+        &self,
+        wallet: Address,
+    ) -> Result<Vec<SuspiciousTransfer>, Box<dyn std::error::Error>> {
+        let transfers = self
+            .explorer_client
+            .get_erc20_token_transfer_events(
+                TokenQueryOption::ByAddress(wallet),
+                Some(TxListParams::new(0, 99999999, 1, 1000, Sort::Desc)),
+            )
+            .await?;
+
+        let mut trusted: Vec<(Address, String)> = vec![(wallet, "this wallet".to_string())];
+        for info in self.whitelist.get_wallet_addresses().values() {
+            if let (true, Ok(address)) = (
+                info.applies_to(self.config.chain_id),
+                Address::from_str(&info.address),
+            ) {
+                let name = info.name.as_deref().unwrap_or("whitelisted");
+                trusted.push((address, name.to_string()));
+            }
+        }
+        for transfer in &transfers {
+            if let (true, false, Some(to)) = (
+                transfer.from == wallet,
+                transfer.value.is_zero(),
+                transfer.to,
+            ) {
+                trusted.push((to, "previous recipient".to_string()));
+            }
+        }
+
+        let mut suspicious = Vec::new();
+        for transfer in transfers.iter().filter(|t| t.value.is_zero()) {
+            let counterparty = match transfer.to {
+                Some(to) if transfer.from == wallet => to,
+                _ => transfer.from,
+            };
+            let mimicked = trusted.iter().find_map(|(address, label)| {
+                lookalike::similarity(counterparty, *address)
+                    .map(|similarity| (*address, label, similarity))
+            });
+            if let Some((mimics, label, similarity)) = mimicked {
+                suspicious.push(SuspiciousTransfer {
+                    tx_hash: transfer.hash,
+                    token_symbol: transfer.token_symbol.clone(),
+                    counterparty,
+                    mimics,
+                    mimics_label: label.clone(),
+                    similarity,
+                });
+            }
+        }
+        Ok(suspicious)
+    }
+
+    pub async fn scan_address_poisoning(
+        &self,
+        wallet: Address,
+        resolver: &Resolver,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let transfers = self.find_suspicious_transfers(wallet).await?;
+        let names = resolver
+            .lookup_names(
+                std::iter::once(wallet).chain(transfers.iter().map(|t| t.counterparty)),
+                self.config.chain,
+            )
+            .await;
+        let wallet = utils::format_named_address(wallet, &names);
+        if transfers.is_empty() {
+            println!(
+                "No lookalike zero-value transfers found for {} on {}",
+                wallet, self.network
+            );
+            return Ok(());
+        }
+        println!(
+            "Zero-value transfers from lookalike addresses for {} on {}:",
+            wallet, self.network
+        );
+        utils::print_suspicious_transfers(&transfers, &names);
+        println!("Never copy a recipient from these transactions.");
+        Ok(())
+    }
+
+    fn ensure_token_whitelisted(
+        &self,
+        token: Address,
+        label: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self
+            .whitelist
+            .is_token_whitelisted(token, self.config.chain_id)
+        {
+            return Ok(());
+        }
+        match self
             .whitelist
-            .is_wallet_whitelisted(&to_address.to_string())
+            .token_active_from(token, self.config.chain_id)
         {
-            return Err("Recipient address is not whitelisted".into());
+            Some(active_from) => Err(format!(
+                "{} address {:?} is pending whitelist activation for another {}",
+                label,
+                token,
+                utils::format_duration(active_from.saturating_sub(utils::unix_now()))
+            )
+            .into()),
+            None => Err(format!("{} address is not whitelisted", label).into()),
         }
+    }
 
-        let amount = U256::from(amount);
+    /// Decimals of a token, read from the whitelist cache when recorded and
+    /// from the chain otherwise.
+    async fn token_decimals(&self, token: Address) -> Result<u8, Box<dyn std::error::Error>> {
+        if let Some(decimals) = self.whitelist.token_decimals(token, self.config.chain_id) {
+            return Ok(decimals);
+        }
+        Ok(ERC20::new(token, self.config.http.clone())
+            .decimals()
+            .call()
+            .await?)
+    }
 
-        let token = ERC20::new(token_address, self.config.http.clone());
-        let tx = token.transfer(to_address, amount);
+    /// Parses a decimal amount such as `1.5` into raw units of `token`.
+    async fn parse_token_amount(
+        &self,
+        token: Address,
+        amount: &str,
+    ) -> Result<U256, Box<dyn std::error::Error>> {
+        let decimals = self.token_decimals(token).await?;
+        Ok(ethers::utils::parse_units(amount, decimals as u32)?.into())
+    }
 
-        let pending_tx = tx.send().await?;
-        println!("Transaction sent: {:?}", pending_tx.tx_hash());
+    /// Formats a raw amount of `token` with its decimals and symbol, read
+    /// from the chain when the token is not whitelisted.
+    async fn format_token_amount(
+        &self,
+        token: Address,
+        amount: U256,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if self
+            .whitelist
+            .token_decimals(token, self.config.chain_id)
+            .is_some()
+        {
+            return Ok(self
+                .whitelist
+                .format_amount(Some(token), self.config.chain_id, amount));
+        }
+        let contract = ERC20::new(token, self.config.http.clone());
+        let decimals = contract.decimals().call().await?;
+        let symbol = contract
+            .symbol()
+            .call()
+            .await
+            .unwrap_or_else(|_| format!("{:?}", token));
+        Ok(format!(
+            "{} {}",
+            ethers::utils::format_units(amount, decimals as u32)?,
+            symbol
+        ))
+    }
 
-        let receipt = pending_tx.await?;
-        println!("Transaction receipt: {:?}", receipt);
+    /// Symbol of `token`, read from the chain when the token is not
+    /// whitelisted.
+    async fn token_symbol(&self, token: Address) -> String {
+        if let Some(info) = self.whitelist.get_token(token, self.config.chain_id) {
+            return info.symbol.clone();
+        }
+        ERC20::new(token, self.config.http.clone())
+            .symbol()
+            .call()
+            .await
+            .unwrap_or_else(|_| format!("{:?}", token))
+    }
+
+    fn enforce_spending_policy(
+        &self,
+        token: Option<Address>,
+        recipient: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        policy::enforce(
+            &self.whitelist,
+            self.config.chain_id,
+            token,
+            recipient,
+            amount,
+        )
+    }
+
+    fn record_spend(
+        &self,
+        kind: &str,
+        tx_hash: H256,
+        token: Option<Address>,
+        recipient: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        journal::record(&JournalEntry::new(
+            self.config.chain_id,
+            tx_hash,
+            kind,
+            self.config.http.address(),
+            token,
+            recipient,
+            amount,
+        ))
+    }
+
+    async fn allowance(
+        &self,
+        token: Address,
+        spender: Address,
+    ) -> Result<U256, Box<dyn std::error::Error>> {
+        let owner = self.config.http.address();
+        Ok(ERC20::new(token, self.config.http.clone())
+            .allowance(owner, spender)
+            .call()
+            .await?)
+    }
+
+    /// Approves `spender` for `amount` of `token`, or for the maximum in
+    /// unlimited mode, unless the current allowance already covers it.
+    /// Tokens such as USDT refuse to change a non-zero allowance, it is reset
+    /// to zero first when a simulated approval reverts.
+    async fn ensure_allowance(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let allowance = self.allowance(token, spender).await?;
+        if allowance >= amount {
+            println!(
+                "The allowance of {:?} for {:?} already covers {}",
+                token,
+                spender,
+                self.format_token_amount(token, amount).await?
+            );
+            return Ok(());
+        }
+
+        // The USDT ABI declares no return value for approve, which also
+        // accepts the boolean of standard tokens
+        let token_contract = USDT::new(token, self.config.http.clone());
+        let approved = self.approval_mode.amount(amount);
+        if !allowance.is_zero()
+            && token_contract
+                .approve(spender, approved)
+                .call()
+                .await
+                .is_err()
+        {
+            println!(
+                "{:?} refuses to change a non-zero allowance, resetting it first",
+                token
+            );
+            self.send_approval(&token_contract, spender, U256::zero())
+                .await?;
+        }
+        if approved == U256::MAX {
+            println!(
+                "Approving an unlimited allowance of {:?} for {:?}",
+                token, spender
+            );
+        }
+        self.send_approval(&token_contract, spender, approved).await
+    }
 
+    async fn send_approval(
+        &self,
+        token: &USDT<HttpSigner>,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let approve_tx = token.approve(spender, amount);
+        let pending_approve_tx = approve_tx.send().await?;
+        println!(
+            "Approval transaction sent: {:?}",
+            pending_approve_tx.tx_hash()
+        );
+        let approve_receipt = pending_approve_tx.await?;
+        println!("Approval transaction receipt: {:?}", approve_receipt);
         Ok(())
     }
 
+    /// Signs an EIP-2612 permit of `value` for `spender` when permits are
+    /// the approval mode and `token` exposes a domain separator and nonces.
+    async fn sign_permit(
+        &self,
+        token: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<Option<Signature>, Box<dyn std::error::Error>> {
+        if self.approval_mode != ApprovalMode::Permit {
+            return Ok(None);
+        }
+        let owner = self.config.http.address();
+        let permit_token = IERC20Permit::new(token, self.config.http.clone());
+        let (Ok(domain_separator), Ok(nonce)) = (
+            permit_token.domain_separator().call().await,
+            permit_token.nonces(owner).call().await,
+        ) else {
+            println!(
+                "{:?} does not support EIP-2612 permits, approving instead",
+                token
+            );
+            return Ok(None);
+        };
+        let digest = approval::permit_digest(
+            domain_separator,
+            approval::permit_typehash(),
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+        );
+        Ok(Some(self.config.http.signer().sign_hash(digest)?))
+    }
+
     pub async fn get_transactions(
         &self,
-        address: String,
+        address: Address,
+        resolver: &Resolver,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let address = Address::from_str(&address)?;
         let transactions = self.find_all_transactions(address).await?;
+        let names = resolver
+            .lookup_names(
+                transactions
+                    .iter()
+                    .flat_map(|tx| std::iter::once(tx.from).chain(tx.to)),
+                self.config.chain,
+            )
+            .await;
 
-        println!("Transactions for address {} on {}:", address, self.network);
-        utils::print_txs(&transactions);
+        println!(
+            "Transactions for address {:?} on {}:",
+            address, self.network
+        );
+        utils::print_txs(&transactions, &names);
 
         Ok(())
     }
@@ -460,216 +954,2817 @@ impl EVMInterface {
         Ok(transactions)
     }
 
-    pub async fn swap_tokens_uniswap_v3(
+    fn uniswap_v3_factory(
         &self,
-        token_in: String,
-        token_out: String,
-        amount_in: String,
-        amount_out_minimum: String,
-        recipient: String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let token_in = Address::from_str(&token_in)?;
-        let token_out = Address::from_str(&token_out)?;
-        let recipient = Address::from_str(&recipient)?;
+    ) -> Result<UNISWAP_V3_FACTORY<HttpSigner>, Box<dyn std::error::Error>> {
+        let factory_address =
+            addressbook::contract_address("uniswap_v3_factory", self.config.chain)
+                .ok_or_else(|| format!("Uniswap V3 Factory not deployed on {}", self.network))?;
+        Ok(UNISWAP_V3_FACTORY::new(
+            factory_address,
+            self.config.http.clone(),
+        ))
+    }
 
-        if !self
-            .whitelist
-            .is_token_whitelisted(&token_in.to_string(), self.config.chain_id)
-        {
-            return Err("Input token address is not whitelisted".into());
+    fn uniswap_v3_quoter(
+        &self,
+    ) -> Result<UNISWAP_V3_QUOTER_V2<HttpSigner>, Box<dyn std::error::Error>> {
+        let quoter_address =
+            addressbook::contract_address("uniswap_v3_quoter_v2", self.config.chain)
+                .ok_or_else(|| format!("Uniswap V3 QuoterV2 not deployed on {}", self.network))?;
+        Ok(UNISWAP_V3_QUOTER_V2::new(
+            quoter_address,
+            self.config.http.clone(),
+        ))
+    }
+
+    fn uniswap_v2_router(
+        &self,
+    ) -> Result<UNISWAP_V2_ROUTER<HttpSigner>, Box<dyn std::error::Error>> {
+        let router_address = addressbook::contract_address("uniswap_v2_router", self.config.chain)
+            .ok_or_else(|| format!("Uniswap V2 Router not deployed on {}", self.network))?;
+        Ok(UNISWAP_V2_ROUTER::new(
+            router_address,
+            self.config.http.clone(),
+        ))
+    }
+
+    fn uniswap_v2_factory(
+        &self,
+    ) -> Result<UNISWAP_V2_FACTORY<HttpSigner>, Box<dyn std::error::Error>> {
+        let factory_address =
+            addressbook::contract_address("uniswap_v2_factory", self.config.chain)
+                .ok_or_else(|| format!("Uniswap V2 Factory not deployed on {}", self.network))?;
+        Ok(UNISWAP_V2_FACTORY::new(
+            factory_address,
+            self.config.http.clone(),
+        ))
+    }
+
+    /// Quotes swapping along `route`, with `amount` in raw units of the
+    /// input token for exact input swaps and of the output token otherwise.
+    async fn get_uniswap_quote(
+        &self,
+        route: &Route,
+        kind: SwapKind,
+        amount: U256,
+    ) -> Result<Quote, Box<dyn std::error::Error>> {
+        let provider = self.config.http.clone();
+        match (route, kind) {
+            (Route::V2(tokens), SwapKind::ExactInput) => {
+                let (router, factory) = (self.uniswap_v2_router()?, self.uniswap_v2_factory()?);
+                uniswap::quote_v2(&router, &factory, provider, tokens, amount).await
+            }
+            (Route::V2(tokens), SwapKind::ExactOutput) => {
+                let (router, factory) = (self.uniswap_v2_router()?, self.uniswap_v2_factory()?);
+                uniswap::quote_v2_exact_output(&router, &factory, provider, tokens, amount).await
+            }
+            (Route::V3(path), SwapKind::ExactInput) => {
+                let (quoter, factory) = (self.uniswap_v3_quoter()?, self.uniswap_v3_factory()?);
+                uniswap::quote_v3(&quoter, &factory, provider, path, amount).await
+            }
+            (Route::V3(path), SwapKind::ExactOutput) => {
+                let (quoter, factory) = (self.uniswap_v3_quoter()?, self.uniswap_v3_factory()?);
+                uniswap::quote_v3_exact_output(&quoter, &factory, provider, path, amount).await
+            }
         }
+    }
 
-        if !self
-            .whitelist
-            .is_token_whitelisted(&token_out.to_string(), self.config.chain_id)
-        {
-            return Err("Output token address is not whitelisted".into());
+    /// Prints the pools used by a quote, its expected amounts with their
+    /// slippage limit and its price impact.
+    async fn print_quote(
+        &self,
+        quote: &Quote,
+        slippage_bps: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        println!("{} route:", quote.version);
+        for hop in &quote.hops {
+            println!(
+                "  {:?} -> {:?} via pool {:?} ({})",
+                hop.token_in,
+                hop.token_out,
+                hop.pool,
+                uniswap::format_fee(hop.fee)
+            );
+        }
+        let token_in = quote.hops[0].token_in;
+        let token_out = quote.hops[quote.hops.len() - 1].token_out;
+        match quote.kind {
+            SwapKind::ExactInput => {
+                println!(
+                    "Amount in:       {}",
+                    self.format_token_amount(token_in, quote.amount_in).await?
+                );
+                println!(
+                    "Expected output: {}",
+                    self.format_token_amount(token_out, quote.amount_out)
+                        .await?
+                );
+                println!(
+                    "Minimum output:  {} ({} slippage)",
+                    self.format_token_amount(token_out, quote.minimum_out(slippage_bps))
+                        .await?,
+                    uniswap::format_bps(slippage_bps)
+                );
+            }
+            SwapKind::ExactOutput => {
+                println!(
+                    "Amount out:      {}",
+                    self.format_token_amount(token_out, quote.amount_out)
+                        .await?
+                );
+                println!(
+                    "Expected input:  {}",
+                    self.format_token_amount(token_in, quote.amount_in).await?
+                );
+                println!(
+                    "Maximum input:   {} ({} slippage)",
+                    self.format_token_amount(token_in, quote.maximum_in(slippage_bps))
+                        .await?,
+                    uniswap::format_bps(slippage_bps)
+                );
+            }
         }
+        println!("Price impact:    {:.2}%", quote.price_impact() * 100.0);
+        if let Some(gas_estimate) = quote.gas_estimate {
+            println!("Gas estimate:    {}", gas_estimate);
+        }
+        Ok(())
+    }
+
+    pub async fn quote_uniswap(
+        &self,
+        route: Route,
+        kind: SwapKind,
+        amount: String,
+        slippage_bps: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let amount_token = match kind {
+            SwapKind::ExactInput => route.token_in(),
+            SwapKind::ExactOutput => route.token_out(),
+        };
+        let amount = self.parse_token_amount(amount_token, &amount).await?;
+        let quote = self.get_uniswap_quote(&route, kind, amount).await?;
+        self.print_quote(&quote, slippage_bps).await
+    }
 
-        if !self.whitelist.is_wallet_whitelisted(&recipient.to_string()) {
-            return Err("Recipient address is not whitelisted".into());
+    /// Builds a single-hop path, using `fee` if given and otherwise the fee
+    /// tier of the pool with the most in-range liquidity.
+    pub async fn resolve_v3_path(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: Option<u32>,
+    ) -> Result<SwapPath, Box<dyn std::error::Error>> {
+        if let Some(fee) = fee {
+            return Ok(SwapPath::single(token_in, token_out, fee));
         }
+        let factory = self.uniswap_v3_factory()?;
+        let (fee, pool, liquidity) =
+            uniswap::deepest_pool(&factory, self.config.http.clone(), token_in, token_out)
+                .await?
+                .ok_or_else(|| {
+                    format!(
+                        "No Uniswap V3 pool for {:?} and {:?} on {}",
+                        token_in, token_out, self.network
+                    )
+                })?;
+        println!(
+            "Selected the {} pool {:?} with the most liquidity ({})",
+            uniswap::format_fee(fee),
+            pool,
+            liquidity
+        );
+        Ok(SwapPath::single(token_in, token_out, fee))
+    }
+
+    /// Swaps along `path`, either selling exactly `amount` of the input token
+    /// or buying exactly `amount` of the output token. `limit` overrides the
+    /// minimum output or maximum input otherwise derived from the quote.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn swap_tokens_uniswap_v3(
+        &self,
+        path: SwapPath,
+        kind: SwapKind,
+        amount: String,
+        limit: Option<String>,
+        slippage_bps: u32,
+        twap_guard: Option<TwapGuard>,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let token_in = path.token_in();
+        let token_out = path.token_out();
 
-        // Create ERC20 instances for token_in and token_out
-        let token_in_contract = ERC20::new(token_in, self.config.http.clone());
-        let token_out_contract = ERC20::new(token_out, self.config.http.clone());
+        self.ensure_token_whitelisted(token_in, "Input token")?;
 
-        // Get token decimals
-        let token_in_decimals = token_in_contract.decimals().call().await?;
-        let token_out_decimals = token_out_contract.decimals().call().await?;
+        self.ensure_token_whitelisted(token_out, "Output token")?;
+
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
 
         // Parse amounts considering token decimals
-        let amount_in =
-            U256::from_dec_str(&amount_in)? * U256::from(10).pow(U256::from(token_in_decimals));
-        let amount_out_minimum = U256::from_dec_str(&amount_out_minimum)?
-            * U256::from(10).pow(U256::from(token_out_decimals));
+        let (amount_token, limit_token) = match kind {
+            SwapKind::ExactInput => (token_in, token_out),
+            SwapKind::ExactOutput => (token_out, token_in),
+        };
+        let amount = self.parse_token_amount(amount_token, &amount).await?;
 
         let uniswap_router_address =
-            addressbook::contract_address("uniswap_v3_router", self.config.chain)
-                .ok_or_else(|| format!("Uniswap V3 Router not deployed on {}", self.network))?;
+            addressbook::contract_address("uniswap_v3_swap_router", self.config.chain)
+                .ok_or_else(|| format!("Uniswap V3 SwapRouter not deployed on {}", self.network))?;
 
-        // Approve token_in for Uniswap router
-        let approve_tx = token_in_contract.approve(uniswap_router_address, amount_in);
-        let pending_approve_tx = approve_tx.send().await?;
-        println!(
-            "Approval transaction sent: {:?}",
-            pending_approve_tx.tx_hash()
-        );
-        let approve_receipt = pending_approve_tx.await?;
-        println!("Approval transaction receipt: {:?}", approve_receipt);
+        // Quote the swap before approving anything, which also checks that
+        // every hop has a pool
+        let quote = self
+            .get_uniswap_quote(&Route::V3(path.clone()), kind, amount)
+            .await?;
+        self.print_quote(&quote, slippage_bps).await?;
+        if let Some(guard) = twap_guard {
+            self.check_twap_deviation(&quote, guard).await?;
+        }
+        let limit = match limit {
+            Some(limit) => {
+                let limit = self.parse_token_amount(limit_token, &limit).await?;
+                let beyond_quote = match kind {
+                    SwapKind::ExactInput => limit > quote.amount_out,
+                    SwapKind::ExactOutput => limit < quote.amount_in,
+                };
+                if beyond_quote {
+                    println!(
+                        "Warning: the limit {} is worse than the quote for the swap to succeed, it will revert unless the price moves in your favour",
+                        self.format_token_amount(limit_token, limit).await?
+                    );
+                }
+                limit
+            }
+            None => match kind {
+                SwapKind::ExactInput => quote.minimum_out(slippage_bps),
+                SwapKind::ExactOutput => quote.maximum_in(slippage_bps),
+            },
+        };
+
+        // Most that can leave the wallet, which is also all that is approved
+        let max_in = match kind {
+            SwapKind::ExactInput => amount,
+            SwapKind::ExactOutput => limit,
+        };
+        self.enforce_spending_policy(Some(token_in), recipient, max_in)?;
 
         let uniswap_router =
             UNISWAP_V3_ROUTER::new(uniswap_router_address, self.config.http.clone());
 
-        let path = ethers::abi::encode(&[
-            Token::Address(token_in),
-            Token::Uint(U256::from(3000)),
-            Token::Address(token_out),
-        ]);
-
-        let params = ExactInputParams(
-            Bytes::from(path),
-            recipient,
-            U256::from(u64::MAX), // Set a reasonable deadline
-            amount_in,
-            amount_out_minimum,
-        );
+        let deadline = U256::from(utils::unix_now() + 20 * 60);
+        let swap = match kind {
+            SwapKind::ExactInput => uniswap_router.exact_input(ExactInputParams(
+                path.encode(),
+                recipient,
+                deadline,
+                amount,
+                limit,
+            )),
+            SwapKind::ExactOutput => uniswap_router.exact_output(ExactOutputParams(
+                path.encode_reversed(),
+                recipient,
+                deadline,
+                amount,
+                limit,
+            )),
+        };
 
-        let tx = uniswap_router.exact_input(params);
-        let pending_tx = tx.send().await?;
+        // The router can pull the input through a permit signed for this
+        // swap, batched with it through multicall
+        let permit = if self.allowance(token_in, uniswap_router_address).await? >= max_in {
+            None
+        } else {
+            self.sign_permit(token_in, uniswap_router_address, max_in, deadline)
+                .await?
+        };
+        let tx = match permit {
+            Some(signature) => {
+                let (v, r, s) = approval::split_signature(&signature);
+                let self_permit = uniswap_router
+                    .self_permit(token_in, max_in, deadline, v, r, s)
+                    .calldata()
+                    .ok_or("Could not encode selfPermit")?;
+                let swap_call = swap.calldata().ok_or("Could not encode the swap")?;
+                let multicall = uniswap_router.multicall(vec![self_permit, swap_call]);
+                match multicall.call().await {
+                    Ok(_) => multicall.tx,
+                    Err(e) => {
+                        println!(
+                            "The swap with a permit fails to simulate ({}), approving instead",
+                            e
+                        );
+                        self.ensure_allowance(token_in, uniswap_router_address, max_in)
+                            .await?;
+                        swap.tx
+                    }
+                }
+            }
+            None => {
+                self.ensure_allowance(token_in, uniswap_router_address, max_in)
+                    .await?;
+                swap.tx
+            }
+        };
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
         println!("Swap transaction sent: {:?}", pending_tx.tx_hash());
+        self.record_spend(
+            "swap_uniswap_v3",
+            pending_tx.tx_hash(),
+            Some(token_in),
+            recipient,
+            max_in,
+        )?;
 
-        let receipt = pending_tx.await?;
+        let receipt = pending_tx
+            .await?
+            .ok_or("Swap transaction was dropped from the mempool")?;
         println!("Swap transaction receipt: {:?}", receipt);
+        if receipt.status != Some(U64::from(1)) {
+            return Err(format!("Swap transaction {:?} reverted", receipt.transaction_hash).into());
+        }
+
+        match uniswap::swapped_amounts(&receipt, &quote.hops) {
+            Some((spent, received)) => {
+                println!(
+                    "Spent {}, received {}",
+                    self.format_token_amount(token_in, spent).await?,
+                    self.format_token_amount(token_out, received).await?
+                );
+                if kind == SwapKind::ExactOutput && spent < max_in {
+                    println!(
+                        "{} of the maximum input was not used and remains approved to the router",
+                        self.format_token_amount(token_in, max_in - spent).await?
+                    );
+                }
+            }
+            None => println!("Could not find the Swap logs of the route in the receipt"),
+        }
 
         Ok(())
     }
 
-    pub async fn add_liquidity_uniswap_v2(
+    /// Swaps along the V2 pairs of `path`. `native_in` and `native_out` send
+    /// or receive ETH instead of the WETH at that end of the path. The slippage
+    /// limit is derived from the router's quote.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn swap_tokens_uniswap_v2(
         &self,
-        token_a: String,
-        token_b: String,
-        amount_a_desired: String,
-        amount_a_min: String,
-        to: String,
-        deadline: u64,
+        path: Vec<Address>,
+        kind: SwapKind,
+        amount: String,
+        native_in: bool,
+        native_out: bool,
+        fee_on_transfer: bool,
+        slippage_bps: u32,
+        twap_guard: Option<TwapGuard>,
+        recipient: Address,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let token_a = Address::from_str(&token_a)?;
-        let token_b = Address::from_str(&token_b)?;
-        let to = Address::from_str(&to)?;
+        let token_in = path[0];
+        let token_out = path[path.len() - 1];
 
-        if !self
-            .whitelist
-            .is_token_whitelisted(&token_a.to_string(), self.config.chain_id)
-        {
-            return Err("Token A address is not whitelisted".into());
+        if !native_in {
+            self.ensure_token_whitelisted(token_in, "Input token")?;
+        }
+        if !native_out {
+            self.ensure_token_whitelisted(token_out, "Output token")?;
         }
 
-        if !self
-            .whitelist
-            .is_token_whitelisted(&token_b.to_string(), self.config.chain_id)
-        {
-            return Err("Token B address is not whitelisted".into());
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
+
+        let router = self.uniswap_v2_router()?;
+        if native_in || native_out {
+            let weth = router.weth().call().await?;
+            if (native_in && token_in != weth) || (native_out && token_out != weth) {
+                return Err(format!(
+                    "ETH swaps must start or end with the router's WETH {:?}",
+                    weth
+                )
+                .into());
+            }
         }
 
-        if !self.whitelist.is_wallet_whitelisted(&to.to_string()) {
-            return Err("Recipient address is not whitelisted".into());
+        let fee_on_transfer = fee_on_transfer || {
+            let flagged: Vec<Address> = path
+                .iter()
+                .copied()
+                .filter(|token| {
+                    self.whitelist
+                        .get_token(*token, self.config.chain_id)
+                        .is_some_and(|info| info.token_type == TokenType::FeeOnTransfer)
+                })
+                .collect();
+            for token in &flagged {
+                println!(
+                    "{:?} is whitelisted as fee-on-transfer, using the supporting router functions",
+                    token
+                );
+            }
+            !flagged.is_empty()
+        };
+        if fee_on_transfer && kind == SwapKind::ExactOutput {
+            return Err("Fee-on-transfer tokens can only be swapped with an exact input".into());
         }
 
-        let token_a_contract = ERC20::new(token_a, self.config.http.clone());
-        let token_b_contract = ERC20::new(token_b, self.config.http.clone());
+        let amount_token = match kind {
+            SwapKind::ExactInput => token_in,
+            SwapKind::ExactOutput => token_out,
+        };
+        let amount = self.parse_token_amount(amount_token, &amount).await?;
+        let quote = self
+            .get_uniswap_quote(&Route::V2(path.clone()), kind, amount)
+            .await?;
+        self.print_quote(&quote, slippage_bps).await?;
+        if let Some(guard) = twap_guard {
+            self.check_twap_deviation(&quote, guard).await?;
+        }
+        if fee_on_transfer {
+            println!("Warning: the quote does not account for transfer fees, raise --slippage-bps if the swap reverts");
+        }
 
-        let token_a_decimals = token_a_contract.decimals().call().await?;
-        let token_b_decimals = token_b_contract.decimals().call().await?;
+        // Most that can leave the wallet: the exact input, or the input
+        // limit of an exact output swap
+        let (amount_in, amount_out) = match kind {
+            SwapKind::ExactInput => (quote.amount_in, quote.minimum_out(slippage_bps)),
+            SwapKind::ExactOutput => (quote.maximum_in(slippage_bps), quote.amount_out),
+        };
+        let spent_token = (!native_in).then_some(token_in);
+        self.enforce_spending_policy(spent_token, recipient, amount_in)?;
 
-        let amount_a_desired = U256::from_dec_str(&amount_a_desired)?
-            * U256::from(10).pow(U256::from(token_a_decimals));
-        let amount_a_min =
-            U256::from_dec_str(&amount_a_min)? * U256::from(10).pow(U256::from(token_a_decimals));
+        if !native_in {
+            self.ensure_allowance(token_in, router.address(), amount_in)
+                .await?;
+        }
 
-        let uniswap_factory_address =
-            addressbook::contract_address("uniswap_v2_factory", self.config.chain)
-                .ok_or_else(|| format!("Uniswap V2 Factory not deployed on {}", self.network))?;
+        let deadline = U256::from(utils::unix_now() + 20 * 60);
+        let tx = match (kind, native_in, native_out, fee_on_transfer) {
+            (SwapKind::ExactInput, true, _, false) => {
+                router
+                    .swap_exact_eth_for_tokens(amount_out, path, recipient, deadline)
+                    .value(amount_in)
+                    .tx
+            }
+            (SwapKind::ExactInput, true, _, true) => {
+                router
+                    .swap_exact_eth_for_tokens_supporting_fee_on_transfer_tokens(
+                        amount_out, path, recipient, deadline,
+                    )
+                    .value(amount_in)
+                    .tx
+            }
+            (SwapKind::ExactInput, false, true, false) => {
+                router
+                    .swap_exact_tokens_for_eth(amount_in, amount_out, path, recipient, deadline)
+                    .tx
+            }
+            (SwapKind::ExactInput, false, true, true) => {
+                router
+                    .swap_exact_tokens_for_eth_supporting_fee_on_transfer_tokens(
+                        amount_in, amount_out, path, recipient, deadline,
+                    )
+                    .tx
+            }
+            (SwapKind::ExactInput, false, false, false) => {
+                router
+                    .swap_exact_tokens_for_tokens(amount_in, amount_out, path, recipient, deadline)
+                    .tx
+            }
+            (SwapKind::ExactInput, false, false, true) => {
+                router
+                    .swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
+                        amount_in, amount_out, path, recipient, deadline,
+                    )
+                    .tx
+            }
+            // The router refunds the ETH sent above the amount actually needed
+            (SwapKind::ExactOutput, true, _, _) => {
+                router
+                    .swap_eth_for_exact_tokens(amount_out, path, recipient, deadline)
+                    .value(amount_in)
+                    .tx
+            }
+            (SwapKind::ExactOutput, false, true, _) => {
+                router
+                    .swap_tokens_for_exact_eth(amount_out, amount_in, path, recipient, deadline)
+                    .tx
+            }
+            (SwapKind::ExactOutput, false, false, _) => {
+                router
+                    .swap_tokens_for_exact_tokens(amount_out, amount_in, path, recipient, deadline)
+                    .tx
+            }
+        };
+
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
+        println!("Swap transaction sent: {:?}", pending_tx.tx_hash());
+        self.record_spend(
+            "swap_uniswap_v2",
+            pending_tx.tx_hash(),
+            spent_token,
+            recipient,
+            amount_in,
+        )?;
+
+        let receipt = pending_tx.await?;
+        println!("Swap transaction receipt: {:?}", receipt);
 
-        let uniswap_factory =
-            UNISWAP_V2_FACTORY::new(uniswap_factory_address, self.config.http.clone());
+        Ok(())
+    }
 
-        // Get the pair address
-        let pair_address = uniswap_factory.get_pair(token_a, token_b).call().await?;
+    /// Withdraws `share` of the wallet's liquidity in the V2 pair of `token_a`
+    /// and `token_b`. With `native_b`, `token_b` is the router's WETH and is
+    /// received as ETH. The router is allowed to move the LP tokens through
+    /// an approval, or through an EIP-2612 permit signature with `use_permit`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn remove_liquidity_uniswap_v2(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        share: uniswap::LiquidityShare,
+        native_b: bool,
+        use_permit: bool,
+        slippage_bps: u32,
+        to: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_recipient_whitelisted(to)?;
+        self.ensure_recipient_not_poisoned(to).await?;
 
-        if pair_address == Address::zero() {
-            return Err("Liquidity pool does not exist for the given token pair".into());
+        let router = self.uniswap_v2_router()?;
+        if native_b && router.weth().call().await? != token_b {
+            return Err("ETH liquidity must be paired with the router's WETH".into());
         }
 
-        let pair_contract = UNISWAP_V2_POOL::new(pair_address, self.config.http.clone());
+        let pair_address = self
+            .uniswap_v2_factory()?
+            .get_pair(token_a, token_b)
+            .call()
+            .await?;
+        if pair_address.is_zero() {
+            return Err(format!(
+                "No Uniswap V2 pair for {:?} and {:?} on {}",
+                token_a, token_b, self.network
+            )
+            .into());
+        }
+        let pair = UNISWAP_V2_POOL::new(pair_address, self.config.http.clone());
 
-        // Get current reserves
-        let (reserve_a, reserve_b, _) = pair_contract.get_reserves().call().await?;
+        let owner = self.config.http.address();
+        let balance = pair.balance_of(owner).call().await?;
+        let liquidity = share.of(balance)?;
 
-        // Calculate amount_b based on the current price in the pool
-        let amount_b_desired = if reserve_a == 0 || reserve_b == 0 {
-            amount_a_desired // If the pool is empty, use the same amount as token A
+        // Tokens are paid out in proportion to the share of the LP supply burnt
+        let (reserve_0, reserve_1, _) = pair.get_reserves().call().await?;
+        let total_supply = pair.total_supply().call().await?;
+        let (reserve_a, reserve_b) = if token_a < token_b {
+            (reserve_0, reserve_1)
         } else {
-            amount_a_desired * U256::from(reserve_b) / U256::from(reserve_a)
+            (reserve_1, reserve_0)
         };
+        let amount_a = liquidity * U256::from(reserve_a) / total_supply;
+        let amount_b = liquidity * U256::from(reserve_b) / total_supply;
+        let amount_a_min = uniswap::minimum_out(amount_a, slippage_bps);
+        let amount_b_min = uniswap::minimum_out(amount_b, slippage_bps);
 
-        let amount_b_min = amount_b_desired * 95 / 100; // Set amount_b_min to 95% of amount_b_desired
+        println!(
+            "Removing {} of {} LP tokens from pair {:?}",
+            ethers::utils::format_units(liquidity, 18)?,
+            ethers::utils::format_units(balance, 18)?,
+            pair_address
+        );
+        println!(
+            "Expected: {} and {}",
+            self.format_token_amount(token_a, amount_a).await?,
+            self.format_token_amount(token_b, amount_b).await?
+        );
+        println!(
+            "Minimum:  {} and {} ({} slippage)",
+            self.format_token_amount(token_a, amount_a_min).await?,
+            self.format_token_amount(token_b, amount_b_min).await?,
+            uniswap::format_bps(slippage_bps)
+        );
 
-        let uniswap_router_address =
-            addressbook::contract_address("uniswap_v2_router", self.config.chain)
-                .ok_or_else(|| format!("Uniswap V2 Router not deployed on {}", self.network))?;
+        self.enforce_spending_policy(Some(pair_address), to, liquidity)?;
 
-        let uniswap_router =
-            UNISWAP_V2_ROUTER::new(uniswap_router_address, self.config.http.clone());
+        // The permit signature is only valid until the removal deadline
+        let deadline = U256::from(utils::unix_now() + 20 * 60);
+        let tx = if use_permit || self.approval_mode == ApprovalMode::Permit {
+            let digest =
+                uniswap::v2_permit_digest(&pair, owner, router.address(), liquidity, deadline)
+                    .await?;
+            let signature = self.config.http.signer().sign_hash(digest)?;
+            let (v, r, s) = approval::split_signature(&signature);
+            if native_b {
+                router
+                    .remove_liquidity_eth_with_permit(
+                        token_a,
+                        liquidity,
+                        amount_a_min,
+                        amount_b_min,
+                        to,
+                        deadline,
+                        false,
+                        v,
+                        r,
+                        s,
+                    )
+                    .tx
+            } else {
+                router
+                    .remove_liquidity_with_permit(
+                        token_a,
+                        token_b,
+                        liquidity,
+                        amount_a_min,
+                        amount_b_min,
+                        to,
+                        deadline,
+                        false,
+                        v,
+                        r,
+                        s,
+                    )
+                    .tx
+            }
+        } else {
+            self.ensure_allowance(pair_address, router.address(), liquidity)
+                .await?;
+            if native_b {
+                router
+                    .remove_liquidity_eth(
+                        token_a,
+                        liquidity,
+                        amount_a_min,
+                        amount_b_min,
+                        to,
+                        deadline,
+                    )
+                    .tx
+            } else {
+                router
+                    .remove_liquidity(
+                        token_a,
+                        token_b,
+                        liquidity,
+                        amount_a_min,
+                        amount_b_min,
+                        to,
+                        deadline,
+                    )
+                    .tx
+            }
+        };
 
-        // Approve token A
-        let approve_a_tx = token_a_contract.approve(uniswap_router_address, amount_a_desired);
-        let pending_approve_a_tx = approve_a_tx.send().await?;
-        println!(
-            "Approval transaction for token A sent: {:?}",
-            pending_approve_a_tx.tx_hash()
-        );
-        let approve_a_receipt = pending_approve_a_tx.await?;
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
         println!(
-            "Approval transaction receipt for token A: {:?}",
-            approve_a_receipt
+            "Remove liquidity transaction sent: {:?}",
+            pending_tx.tx_hash()
         );
+        self.record_spend(
+            "remove_liquidity_uniswap_v2",
+            pending_tx.tx_hash(),
+            Some(pair_address),
+            to,
+            liquidity,
+        )?;
+
+        let receipt = pending_tx.await?;
+        println!("Remove liquidity transaction receipt: {:?}", receipt);
+
+        Ok(())
+    }
+
+    /// Adds liquidity to the V2 pair of `token_a` and `token_b`, matching
+    /// `amount_a_desired` with the amount of `token_b` at the pair's price.
+    /// A pair that does not exist yet is created by the router, at
+    /// `initial_price` (`token_b` per `token_a`). With `native_eth`, the side
+    /// that is the router's WETH is paid in ETH.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_liquidity_uniswap_v2(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        amount_a_desired: String,
+        amount_a_min: Option<String>,
+        initial_price: Option<f64>,
+        native_eth: bool,
+        slippage_bps: u32,
+        to: Address,
+        deadline: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let router = self.uniswap_v2_router()?;
+        let weth = if native_eth {
+            let weth = router.weth().call().await?;
+            if token_a != weth && token_b != weth {
+                return Err("ETH liquidity must be paired with the router's WETH".into());
+            }
+            Some(weth)
+        } else {
+            None
+        };
+        let is_native = |token: Address| weth == Some(token);
+
+        for (token, label) in [(token_a, "Token A"), (token_b, "Token B")] {
+            if !is_native(token) {
+                self.ensure_token_whitelisted(token, label)?;
+            }
+        }
+
+        self.ensure_recipient_whitelisted(to)?;
+        self.ensure_recipient_not_poisoned(to).await?;
+
+        let amount_a_desired = self.parse_token_amount(token_a, &amount_a_desired).await?;
+        let amount_a_min = match amount_a_min {
+            Some(amount) => self.parse_token_amount(token_a, &amount).await?,
+            None => uniswap::minimum_out(amount_a_desired, slippage_bps),
+        };
+
+        let pair_address = self
+            .uniswap_v2_factory()?
+            .get_pair(token_a, token_b)
+            .call()
+            .await?;
+        let reserves = if pair_address.is_zero() {
+            None
+        } else {
+            // Reserves are ordered by token address, not by argument order
+            let (reserve_0, reserve_1, _) =
+                UNISWAP_V2_POOL::new(pair_address, self.config.http.clone())
+                    .get_reserves()
+                    .call()
+                    .await?;
+            let (reserve_a, reserve_b) = if token_a < token_b {
+                (reserve_0, reserve_1)
+            } else {
+                (reserve_1, reserve_0)
+            };
+            Some((reserve_a, reserve_b)).filter(|(a, b)| *a != 0 && *b != 0)
+        };
+
+        let amount_b_desired = match (reserves, initial_price) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "Pair {:?} already has liquidity, --initial-price only applies to new pairs",
+                    pair_address
+                )
+                .into())
+            }
+            (Some((reserve_a, reserve_b)), None) => {
+                amount_a_desired * U256::from(reserve_b) / U256::from(reserve_a)
+            }
+            (None, Some(price)) => {
+                if !(price.is_finite() && price > 0.0) {
+                    return Err(format!("Invalid initial price {}", price).into());
+                }
+                let decimals_a = self.token_decimals(token_a).await?;
+                let decimals_b = self.token_decimals(token_b).await?;
+                let amount_a: f64 =
+                    ethers::utils::format_units(amount_a_desired, decimals_a as u32)?.parse()?;
+                ethers::utils::parse_units(
+                    format!("{:.*}", decimals_b as usize, amount_a * price),
+                    decimals_b as u32,
+                )?
+                .into()
+            }
+            (None, None) => {
+                return Err(format!(
+                    "The {:?}/{:?} pair has no liquidity yet, set its price with --initial-price",
+                    token_a, token_b
+                )
+                .into())
+            }
+        };
+        let amount_b_min = uniswap::minimum_out(amount_b_desired, slippage_bps);
 
-        // Approve token B
-        let approve_b_tx = token_b_contract.approve(uniswap_router_address, amount_b_desired);
-        let pending_approve_b_tx = approve_b_tx.send().await?;
+        if reserves.is_none() {
+            // The router creates missing pairs and mints at exactly the
+            // desired amounts, which sets the initial price
+            println!(
+                "{} pair {:?}/{:?} at {} {:?} per {:?}",
+                if pair_address.is_zero() {
+                    "Creating"
+                } else {
+                    "Initializing"
+                },
+                token_a,
+                token_b,
+                initial_price.unwrap_or_default(),
+                token_b,
+                token_a
+            );
+        }
         println!(
-            "Approval transaction for token B sent: {:?}",
-            pending_approve_b_tx.tx_hash()
+            "Adding {} and {}",
+            self.format_token_amount(token_a, amount_a_desired).await?,
+            self.format_token_amount(token_b, amount_b_desired).await?
         );
-        let approve_b_receipt = pending_approve_b_tx.await?;
         println!(
-            "Approval transaction receipt for token B: {:?}",
-            approve_b_receipt
+            "Minimum: {} and {} ({} slippage)",
+            self.format_token_amount(token_a, amount_a_min).await?,
+            self.format_token_amount(token_b, amount_b_min).await?,
+            uniswap::format_bps(slippage_bps)
         );
 
+        let spent_token = |token: Address| (!is_native(token)).then_some(token);
+        self.enforce_spending_policy(spent_token(token_a), to, amount_a_desired)?;
+        self.enforce_spending_policy(spent_token(token_b), to, amount_b_desired)?;
+
+        for (token, amount) in [(token_a, amount_a_desired), (token_b, amount_b_desired)] {
+            if !is_native(token) {
+                self.ensure_allowance(token, router.address(), amount)
+                    .await?;
+            }
+        }
+
         let deadline = if deadline == 0 {
             SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 3600 // Default to 1 hour from now
         } else {
             deadline
         };
 
-        let tx = uniswap_router.add_liquidity(
-            token_a,
-            token_b,
-            amount_a_desired,
-            amount_b_desired,
-            amount_a_min,
-            amount_b_min,
-            to,
-            deadline.into(),
-        );
-        let pending_tx = tx.send().await?;
+        let tx = if is_native(token_a) || is_native(token_b) {
+            // The router takes the token side first and refunds unused ETH
+            let (token, amount_token, amount_token_min, amount_eth, amount_eth_min) =
+                if is_native(token_b) {
+                    (
+                        token_a,
+                        amount_a_desired,
+                        amount_a_min,
+                        amount_b_desired,
+                        amount_b_min,
+                    )
+                } else {
+                    (
+                        token_b,
+                        amount_b_desired,
+                        amount_b_min,
+                        amount_a_desired,
+                        amount_a_min,
+                    )
+                };
+            router
+                .add_liquidity_eth(
+                    token,
+                    amount_token,
+                    amount_token_min,
+                    amount_eth_min,
+                    to,
+                    deadline.into(),
+                )
+                .value(amount_eth)
+                .tx
+        } else {
+            router
+                .add_liquidity(
+                    token_a,
+                    token_b,
+                    amount_a_desired,
+                    amount_b_desired,
+                    amount_a_min,
+                    amount_b_min,
+                    to,
+                    deadline.into(),
+                )
+                .tx
+        };
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
         println!("Add liquidity transaction sent: {:?}", pending_tx.tx_hash());
+        for (token, amount) in [(token_a, amount_a_desired), (token_b, amount_b_desired)] {
+            self.record_spend(
+                "add_liquidity_uniswap_v2",
+                pending_tx.tx_hash(),
+                spent_token(token),
+                to,
+                amount,
+            )?;
+        }
 
         let receipt = pending_tx.await?;
         println!("Add liquidity transaction receipt: {:?}", receipt);
 
         Ok(())
     }
+
+    fn uniswap_v3_position_manager(
+        &self,
+    ) -> Result<UNISWAP_V3_POSITION_MANAGER<HttpSigner>, Box<dyn std::error::Error>> {
+        let manager_address = addressbook::contract_address(
+            "uniswap_v3_nonfungible_position_manager",
+            self.config.chain,
+        )
+        .ok_or_else(|| {
+            format!(
+                "Uniswap V3 NonfungiblePositionManager not deployed on {}",
+                self.network
+            )
+        })?;
+        Ok(UNISWAP_V3_POSITION_MANAGER::new(
+            manager_address,
+            self.config.http.clone(),
+        ))
+    }
+
+    async fn uniswap_v3_pool_state(
+        &self,
+        token0: Address,
+        token1: Address,
+        fee: u32,
+    ) -> Result<PoolState, Box<dyn std::error::Error>> {
+        let pool = uniswap::get_pool(&self.uniswap_v3_factory()?, token0, token1, fee)
+            .await?
+            .ok_or_else(|| {
+                format!(
+                    "No {} Uniswap V3 pool for {:?} and {:?} on {}",
+                    uniswap::format_fee(fee),
+                    token0,
+                    token1,
+                    self.network
+                )
+            })?;
+        uniswap::pool_state(&UNISWAP_V3_POOL::new(pool, self.config.http.clone())).await
+    }
+
+    /// Reads a position and checks that it belongs to the wallet.
+    async fn owned_uniswap_v3_position(
+        &self,
+        manager: &UNISWAP_V3_POSITION_MANAGER<HttpSigner>,
+        token_id: U256,
+    ) -> Result<Position, Box<dyn std::error::Error>> {
+        let owner = manager
+            .owner_of(token_id)
+            .call()
+            .await
+            .map_err(|e| format!("Position #{} does not exist: {}", token_id, e))?;
+        if owner != self.config.http.address() {
+            return Err(format!(
+                "Position #{} belongs to {:?}, not to this wallet",
+                token_id, owner
+            )
+            .into());
+        }
+        uniswap::position(manager, token_id).await
+    }
+
+    /// Prints the pool price, a tick range and the token amounts that
+    /// `liquidity` represents in it.
+    async fn print_v3_range(
+        &self,
+        token0: Address,
+        token1: Address,
+        pool: &PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let decimals0 = self.token_decimals(token0).await?;
+        let decimals1 = self.token_decimals(token1).await?;
+        let symbol0 = self.token_symbol(token0).await;
+        let symbol1 = self.token_symbol(token1).await;
+        let price = |tick| tick_math::tick_to_price(tick, decimals0, decimals1);
+
+        println!(
+            "Pool {:?}: 1 {} = {} {} (tick {}), in-range liquidity {}",
+            pool.address,
+            symbol0,
+            tick_math::sqrt_price_to_price(pool.sqrt_price_x96, decimals0, decimals1),
+            symbol1,
+            pool.tick,
+            pool.liquidity
+        );
+        let status = if pool.tick < tick_lower {
+            format!("out of range, only {} is held", symbol0)
+        } else if pool.tick >= tick_upper {
+            format!("out of range, only {} is held", symbol1)
+        } else {
+            "in range".to_string()
+        };
+        println!(
+            "Range: {} to {} {} per {} (ticks {} to {}), {}",
+            price(tick_lower),
+            price(tick_upper),
+            symbol1,
+            symbol0,
+            tick_lower,
+            tick_upper,
+            status
+        );
+        let (amount0, amount1) =
+            uniswap::range_amounts(pool.sqrt_price_x96, tick_lower, tick_upper, liquidity)?;
+        println!(
+            "Liquidity {}: {} and {}",
+            liquidity,
+            self.format_token_amount(token0, amount0).await?,
+            self.format_token_amount(token1, amount1).await?
+        );
+        Ok(())
+    }
+
+    /// Liquidity and desired amounts for depositing `amount0` and/or
+    /// `amount1` in a range. A missing amount is derived from the other one.
+    fn v3_deposit(
+        pool: &PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0: Option<U256>,
+        amount1: Option<U256>,
+    ) -> Result<(u128, U256, U256), Box<dyn std::error::Error>> {
+        let sqrt_a = tick_math::sqrt_ratio_at_tick(tick_lower)?;
+        let sqrt_b = tick_math::sqrt_ratio_at_tick(tick_upper)?;
+        let price = pool.sqrt_price_x96;
+        match (amount0, amount1) {
+            (Some(_), None) if price >= sqrt_b => {
+                return Err(
+                    "The range is below the current price, only token1 can be deposited".into(),
+                )
+            }
+            (None, Some(_)) if price <= sqrt_a => {
+                return Err(
+                    "The range is above the current price, only token0 can be deposited".into(),
+                )
+            }
+            (None, None) => return Err("An amount of token0 or token1 is required".into()),
+            _ => {}
+        }
+        let liquidity = tick_math::liquidity_for_amounts(
+            price,
+            sqrt_a,
+            sqrt_b,
+            amount0.unwrap_or(U256::MAX),
+            amount1.unwrap_or(U256::MAX),
+        );
+        if liquidity == 0 {
+            return Err("The amounts are too small to provide any liquidity".into());
+        }
+        let (needed0, needed1) = tick_math::amounts_for_liquidity(price, sqrt_a, sqrt_b, liquidity);
+        // The pool rounds the amounts it pulls up, leave one unit of margin
+        let desired = |given: Option<U256>, needed: U256| {
+            given.unwrap_or(if needed.is_zero() { needed } else { needed + 1 })
+        };
+        Ok((
+            liquidity,
+            desired(amount0, needed0),
+            desired(amount1, needed1),
+        ))
+    }
+
+    /// Mints a V3 position between two prices of `token_a` in `token_b` (or
+    /// two pool ticks), depositing `amount_a` and/or `amount_b`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn mint_uniswap_v3_position(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        fee: u32,
+        range: PriceRange,
+        amount_a: Option<String>,
+        amount_b: Option<String>,
+        slippage_bps: u32,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_token_whitelisted(token_a, "Token A")?;
+        self.ensure_token_whitelisted(token_b, "Token B")?;
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
+
+        let a_is_token0 = token_a < token_b;
+        let (token0, token1) = if a_is_token0 {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let pool = self.uniswap_v3_pool_state(token0, token1, fee).await?;
+
+        let (tick_lower, tick_upper) = match range {
+            PriceRange::Ticks(lower, upper) => {
+                if lower % pool.tick_spacing != 0 || upper % pool.tick_spacing != 0 {
+                    return Err(format!(
+                        "Ticks must be multiples of the pool's tick spacing {}",
+                        pool.tick_spacing
+                    )
+                    .into());
+                }
+                (lower, upper)
+            }
+            PriceRange::Prices(lower, upper) => {
+                let decimals0 = self.token_decimals(token0).await?;
+                let decimals1 = self.token_decimals(token1).await?;
+                // Ticks price token0 in token1, invert prices given the other way
+                let (lower, upper) = if a_is_token0 {
+                    (lower, upper)
+                } else {
+                    (1.0 / upper, 1.0 / lower)
+                };
+                (
+                    tick_math::price_to_tick(lower, decimals0, decimals1, pool.tick_spacing)?,
+                    tick_math::price_to_tick(upper, decimals0, decimals1, pool.tick_spacing)?,
+                )
+            }
+        };
+        if tick_lower >= tick_upper {
+            return Err(format!(
+                "Empty range from tick {} to tick {}, widen the range",
+                tick_lower, tick_upper
+            )
+            .into());
+        }
+
+        let amount_a = match amount_a {
+            Some(amount) => Some(self.parse_token_amount(token_a, &amount).await?),
+            None => None,
+        };
+        let amount_b = match amount_b {
+            Some(amount) => Some(self.parse_token_amount(token_b, &amount).await?),
+            None => None,
+        };
+        let (amount0, amount1) = if a_is_token0 {
+            (amount_a, amount_b)
+        } else {
+            (amount_b, amount_a)
+        };
+        let (liquidity, amount0_desired, amount1_desired) =
+            Self::v3_deposit(&pool, tick_lower, tick_upper, amount0, amount1)?;
+        let (expected0, expected1) =
+            uniswap::range_amounts(pool.sqrt_price_x96, tick_lower, tick_upper, liquidity)?;
+        self.print_v3_range(token0, token1, &pool, tick_lower, tick_upper, liquidity)
+            .await?;
+
+        self.enforce_spending_policy(Some(token0), recipient, amount0_desired)?;
+        self.enforce_spending_policy(Some(token1), recipient, amount1_desired)?;
+
+        let manager = self.uniswap_v3_position_manager()?;
+        let deadline = U256::from(utils::unix_now() + 20 * 60);
+        let mint = manager.mint(MintParams {
+            token_0: token0,
+            token_1: token1,
+            fee,
+            tick_lower,
+            tick_upper,
+            amount_0_desired: amount0_desired,
+            amount_1_desired: amount1_desired,
+            amount_0_min: uniswap::minimum_out(expected0, slippage_bps),
+            amount_1_min: uniswap::minimum_out(expected1, slippage_bps),
+            recipient,
+            deadline,
+        });
+        let tx = self
+            .position_manager_transaction(
+                &manager,
+                mint,
+                [(token0, amount0_desired), (token1, amount1_desired)],
+                deadline,
+            )
+            .await?;
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
+        println!("Mint transaction sent: {:?}", pending_tx.tx_hash());
+        for (token, amount) in [(token0, amount0_desired), (token1, amount1_desired)] {
+            self.record_spend(
+                "mint_uniswap_v3_position",
+                pending_tx.tx_hash(),
+                Some(token),
+                recipient,
+                amount,
+            )?;
+        }
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Mint transaction was dropped from the mempool")?;
+        println!("Mint transaction receipt: {:?}", receipt);
+        let minted = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == manager.address())
+            .filter_map(|log| ethers::contract::parse_log::<TransferFilter>(log.clone()).ok())
+            .find(|transfer| transfer.from.is_zero());
+        if let Some(minted) = minted {
+            println!("Minted position #{}", minted.token_id);
+        }
+        self.print_liquidity_change(&receipt, manager.address(), token0, token1)
+            .await
+    }
+
+    /// Builds the transaction of a position manager `call` depositing
+    /// `deposits`. Like the swap router, the manager can pull each token
+    /// through a permit signed for the call and batched with it through
+    /// multicall, tokens without one are approved first.
+    async fn position_manager_transaction<D: Detokenize>(
+        &self,
+        manager: &UNISWAP_V3_POSITION_MANAGER<HttpSigner>,
+        call: ContractCall<HttpSigner, D>,
+        deposits: [(Address, U256); 2],
+        deadline: U256,
+    ) -> Result<TypedTransaction, Box<dyn std::error::Error>> {
+        let mut calls = Vec::new();
+        for (token, amount) in deposits {
+            let permit =
+                if amount.is_zero() || self.allowance(token, manager.address()).await? >= amount {
+                    None
+                } else {
+                    self.sign_permit(token, manager.address(), amount, deadline)
+                        .await?
+                };
+            match permit {
+                Some(signature) => {
+                    let (v, r, s) = approval::split_signature(&signature);
+                    calls.push(
+                        manager
+                            .self_permit(token, amount, deadline, v, r, s)
+                            .calldata()
+                            .ok_or("Could not encode selfPermit")?,
+                    );
+                }
+                None => {
+                    self.ensure_allowance(token, manager.address(), amount)
+                        .await?
+                }
+            }
+        }
+        if calls.is_empty() {
+            return Ok(call.tx);
+        }
+
+        calls.push(
+            call.calldata()
+                .ok_or("Could not encode the position call")?,
+        );
+        let multicall = manager.multicall(calls);
+        match multicall.call().await {
+            Ok(_) => Ok(multicall.tx),
+            Err(e) => {
+                println!(
+                    "The transaction with permits fails to simulate ({}), approving instead",
+                    e
+                );
+                for (token, amount) in deposits {
+                    self.ensure_allowance(token, manager.address(), amount)
+                        .await?;
+                }
+                Ok(call.tx)
+            }
+        }
+    }
+
+    /// Prints the amounts deposited according to the `IncreaseLiquidity` log.
+    async fn print_liquidity_change(
+        &self,
+        receipt: &TransactionReceipt,
+        manager: Address,
+        token0: Address,
+        token1: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if receipt.status != Some(U64::one()) {
+            return Err(format!("Transaction {:?} reverted", receipt.transaction_hash).into());
+        }
+        let increase = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == manager)
+            .find_map(|log| {
+                ethers::contract::parse_log::<IncreaseLiquidityFilter>(log.clone()).ok()
+            });
+        if let Some(increase) = increase {
+            println!(
+                "Added liquidity {}: {} and {}",
+                increase.liquidity,
+                self.format_token_amount(token0, increase.amount_0).await?,
+                self.format_token_amount(token1, increase.amount_1).await?
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds `amount` of one of the position's tokens to a V3 position, along
+    /// with the amount of the other token the range requires.
+    pub async fn increase_uniswap_v3_liquidity(
+        &self,
+        token_id: U256,
+        token: Address,
+        amount: String,
+        slippage_bps: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let manager = self.uniswap_v3_position_manager()?;
+        let position = self.owned_uniswap_v3_position(&manager, token_id).await?;
+        if token != position.token0 && token != position.token1 {
+            return Err(format!(
+                "{:?} is not one of the tokens of position #{}",
+                token, token_id
+            )
+            .into());
+        }
+        self.ensure_token_whitelisted(position.token0, "Token 0")?;
+        self.ensure_token_whitelisted(position.token1, "Token 1")?;
+
+        let pool = self
+            .uniswap_v3_pool_state(position.token0, position.token1, position.fee)
+            .await?;
+        let amount = self.parse_token_amount(token, &amount).await?;
+        let (amount0, amount1) = if token == position.token0 {
+            (Some(amount), None)
+        } else {
+            (None, Some(amount))
+        };
+        let (liquidity, amount0_desired, amount1_desired) = Self::v3_deposit(
+            &pool,
+            position.tick_lower,
+            position.tick_upper,
+            amount0,
+            amount1,
+        )?;
+        let (expected0, expected1) = uniswap::range_amounts(
+            pool.sqrt_price_x96,
+            position.tick_lower,
+            position.tick_upper,
+            liquidity,
+        )?;
+        self.print_v3_range(
+            position.token0,
+            position.token1,
+            &pool,
+            position.tick_lower,
+            position.tick_upper,
+            liquidity,
+        )
+        .await?;
+
+        let owner = self.config.http.address();
+        self.enforce_spending_policy(Some(position.token0), owner, amount0_desired)?;
+        self.enforce_spending_policy(Some(position.token1), owner, amount1_desired)?;
+
+        let deadline = U256::from(utils::unix_now() + 20 * 60);
+        let increase = manager.increase_liquidity(IncreaseLiquidityParams {
+            token_id,
+            amount_0_desired: amount0_desired,
+            amount_1_desired: amount1_desired,
+            amount_0_min: uniswap::minimum_out(expected0, slippage_bps),
+            amount_1_min: uniswap::minimum_out(expected1, slippage_bps),
+            deadline,
+        });
+        let tx = self
+            .position_manager_transaction(
+                &manager,
+                increase,
+                [
+                    (position.token0, amount0_desired),
+                    (position.token1, amount1_desired),
+                ],
+                deadline,
+            )
+            .await?;
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
+        println!(
+            "Increase liquidity transaction sent: {:?}",
+            pending_tx.tx_hash()
+        );
+        for (token, amount) in [
+            (position.token0, amount0_desired),
+            (position.token1, amount1_desired),
+        ] {
+            self.record_spend(
+                "increase_uniswap_v3_liquidity",
+                pending_tx.tx_hash(),
+                Some(token),
+                owner,
+                amount,
+            )?;
+        }
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Increase liquidity transaction was dropped from the mempool")?;
+        println!("Increase liquidity transaction receipt: {:?}", receipt);
+        self.print_liquidity_change(
+            &receipt,
+            manager.address(),
+            position.token0,
+            position.token1,
+        )
+        .await
+    }
+
+    /// Withdraws `share` of a V3 position's liquidity and collects the
+    /// withdrawn tokens, along with the fees owed, to `recipient`.
+    pub async fn decrease_uniswap_v3_liquidity(
+        &self,
+        token_id: U256,
+        share: LiquidityShare,
+        slippage_bps: u32,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
+
+        let manager = self.uniswap_v3_position_manager()?;
+        let position = self.owned_uniswap_v3_position(&manager, token_id).await?;
+        self.ensure_token_whitelisted(position.token0, "Token 0")?;
+        self.ensure_token_whitelisted(position.token1, "Token 1")?;
+        let liquidity = share.of(U256::from(position.liquidity))?.as_u128();
+        if liquidity == 0 {
+            return Err(format!(
+                "No liquidity to remove from position #{}, it holds {}",
+                token_id, position.liquidity
+            )
+            .into());
+        }
+
+        let pool = self
+            .uniswap_v3_pool_state(position.token0, position.token1, position.fee)
+            .await?;
+        let (expected0, expected1) = uniswap::range_amounts(
+            pool.sqrt_price_x96,
+            position.tick_lower,
+            position.tick_upper,
+            liquidity,
+        )?;
+        println!(
+            "Removing {} of {} liquidity from position #{}",
+            liquidity, position.liquidity, token_id
+        );
+        self.print_v3_range(
+            position.token0,
+            position.token1,
+            &pool,
+            position.tick_lower,
+            position.tick_upper,
+            liquidity,
+        )
+        .await?;
+
+        // Decreasing only credits the tokens to the position, collect them
+        // in the same transaction
+        let decrease = manager
+            .decrease_liquidity(DecreaseLiquidityParams {
+                token_id,
+                liquidity,
+                amount_0_min: uniswap::minimum_out(expected0, slippage_bps),
+                amount_1_min: uniswap::minimum_out(expected1, slippage_bps),
+                deadline: U256::from(utils::unix_now() + 20 * 60),
+            })
+            .calldata()
+            .ok_or("Could not encode decreaseLiquidity")?;
+        let collect = manager
+            .collect(CollectParams {
+                token_id,
+                recipient,
+                amount_0_max: u128::MAX,
+                amount_1_max: u128::MAX,
+            })
+            .calldata()
+            .ok_or("Could not encode collect")?;
+        let tx = manager.multicall(vec![decrease, collect]);
+        let pending_tx = tx.send().await?;
+        println!(
+            "Decrease liquidity transaction sent: {:?}",
+            pending_tx.tx_hash()
+        );
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Decrease liquidity transaction was dropped from the mempool")?;
+        println!("Decrease liquidity transaction receipt: {:?}", receipt);
+        if receipt.status != Some(U64::one()) {
+            return Err(format!(
+                "Decrease liquidity transaction {:?} reverted",
+                receipt.transaction_hash
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Collects the fees and withdrawn tokens owed to a V3 position.
+    pub async fn collect_uniswap_v3_fees(
+        &self,
+        token_id: U256,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
+
+        let manager = self.uniswap_v3_position_manager()?;
+        let position = self.owned_uniswap_v3_position(&manager, token_id).await?;
+        self.ensure_token_whitelisted(position.token0, "Token 0")?;
+        self.ensure_token_whitelisted(position.token1, "Token 1")?;
+
+        let tx = manager.collect(CollectParams {
+            token_id,
+            recipient,
+            amount_0_max: u128::MAX,
+            amount_1_max: u128::MAX,
+        });
+        // Simulating the collection also accounts for fees not yet credited
+        let (amount0, amount1) = tx.call().await?;
+        if amount0.is_zero() && amount1.is_zero() {
+            println!("Nothing to collect for position #{}", token_id);
+            return Ok(());
+        }
+        println!(
+            "Collecting {} and {}",
+            self.format_token_amount(position.token0, amount0).await?,
+            self.format_token_amount(position.token1, amount1).await?
+        );
+
+        let pending_tx = tx.send().await?;
+        println!("Collect transaction sent: {:?}", pending_tx.tx_hash());
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Collect transaction was dropped from the mempool")?;
+        println!("Collect transaction receipt: {:?}", receipt);
+        if receipt.status != Some(U64::one()) {
+            return Err(format!(
+                "Collect transaction {:?} reverted",
+                receipt.transaction_hash
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Burns an empty V3 position.
+    pub async fn burn_uniswap_v3_position(
+        &self,
+        token_id: U256,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let manager = self.uniswap_v3_position_manager()?;
+        let position = self.owned_uniswap_v3_position(&manager, token_id).await?;
+        self.ensure_token_whitelisted(position.token0, "Token 0")?;
+        self.ensure_token_whitelisted(position.token1, "Token 1")?;
+        if position.liquidity != 0 || position.tokens_owed0 != 0 || position.tokens_owed1 != 0 {
+            return Err(format!(
+                "Position #{} still holds liquidity or uncollected tokens, decrease and collect it first",
+                token_id
+            )
+            .into());
+        }
+
+        let tx = manager.burn(token_id);
+        let pending_tx = tx.send().await?;
+        println!("Burn transaction sent: {:?}", pending_tx.tx_hash());
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Burn transaction was dropped from the mempool")?;
+        println!("Burn transaction receipt: {:?}", receipt);
+        if receipt.status != Some(U64::one()) {
+            return Err(format!("Burn transaction {:?} reverted", receipt.transaction_hash).into());
+        }
+
+        Ok(())
+    }
+
+    /// `amount` of `token` in whole units.
+    async fn token_units(
+        &self,
+        token: Address,
+        amount: U256,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        let decimals = self.token_decimals(token).await?;
+        Ok(ethers::utils::format_units(amount, decimals as u32)?.parse()?)
+    }
+
+    /// Lists the V2 LP balances of `wallet` in pairs of whitelisted tokens and
+    /// its V3 positions, with their underlying amounts, fees and value
+    /// compared with holding the deposited tokens. Deposit histories are read
+    /// from the block where the wallet minted the LP tokens or the position,
+    /// as found by the explorer, and never before `from_block`.
+    pub async fn lp_positions(
+        &self,
+        wallet: Address,
+        from_block: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.uniswap_v2_factory() {
+            Ok(factory) => {
+                self.print_v2_positions(&factory, wallet, from_block)
+                    .await?
+            }
+            Err(e) => println!("Skipping Uniswap V2: {}", e),
+        }
+        match self.uniswap_v3_position_manager() {
+            Ok(manager) => {
+                self.print_v3_positions(&manager, wallet, from_block)
+                    .await?
+            }
+            Err(e) => println!("Skipping Uniswap V3: {}", e),
+        }
+        Ok(())
+    }
+
+    fn print_position_value(value: f64, hold: Option<f64>, symbol: &str) {
+        match hold {
+            Some(hold) if hold > 0.0 => println!(
+                "  Value: {:.6} {}, holding the deposited tokens: {:.6} {}, impermanent loss: {:.2}%",
+                value,
+                symbol,
+                hold,
+                symbol,
+                (value / hold - 1.0) * 100.0
+            ),
+            _ => println!(
+                "  Value: {:.6} {}, no deposit recorded to compare with",
+                value, symbol
+            ),
+        }
+    }
+
+    async fn print_v2_positions(
+        &self,
+        factory: &UNISWAP_V2_FACTORY<HttpSigner>,
+        wallet: Address,
+        from_block: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tokens: Vec<Address> = self
+            .whitelist
+            .get_token_addresses()
+            .values()
+            .filter(|token| token.chain_id == self.config.chain_id)
+            .filter_map(|token| Address::from_str(&token.address).ok())
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+
+        println!("Uniswap V2 pairs of whitelisted tokens:");
+        let mut found = 0;
+        for (index, &token0) in tokens.iter().enumerate() {
+            for &token1 in &tokens[index + 1..] {
+                let pair_address = factory.get_pair(token0, token1).call().await?;
+                if pair_address.is_zero() {
+                    continue;
+                }
+                let pair = UNISWAP_V2_POOL::new(pair_address, self.config.http.clone());
+                let balance = pair.balance_of(wallet).call().await?;
+                if balance.is_zero() {
+                    continue;
+                }
+                found += 1;
+
+                let (reserve_0, reserve_1, _) = pair.get_reserves().call().await?;
+                let total_supply = pair.total_supply().call().await?;
+                let amount0 = balance * U256::from(reserve_0) / total_supply;
+                let amount1 = balance * U256::from(reserve_1) / total_supply;
+                let symbol0 = self.token_symbol(token0).await;
+                let symbol1 = self.token_symbol(token1).await;
+                println!(
+                    "{}/{} pair {:?}: {} LP tokens ({:.4}% of the supply)",
+                    symbol0,
+                    symbol1,
+                    pair_address,
+                    ethers::utils::format_units(balance, 18)?,
+                    uniswap::to_f64(balance) / uniswap::to_f64(total_supply) * 100.0
+                );
+                println!(
+                    "  Underlying: {} and {}, fees are compounded into the reserves",
+                    self.format_token_amount(token0, amount0).await?,
+                    self.format_token_amount(token1, amount1).await?
+                );
+
+                let units0 = self.token_units(token0, amount0).await?;
+                let units1 = self.token_units(token1, amount1).await?;
+                let price = self.token_units(token1, U256::from(reserve_1)).await?
+                    / self.token_units(token0, U256::from(reserve_0)).await?;
+                let value = units0 * price + units1;
+                let hold = match self
+                    .v2_entry_price(&pair, wallet, token0, token1, from_block)
+                    .await
+                {
+                    Ok(entry_price) => entry_price.map(|entry_price| {
+                        value / (1.0 + uniswap::impermanent_loss_v2(price / entry_price))
+                    }),
+                    Err(e) => {
+                        println!("  Could not read the deposit history: {}", e);
+                        None
+                    }
+                };
+                Self::print_position_value(value, hold, &symbol1);
+            }
+        }
+        if found == 0 {
+            println!("  No LP balance");
+        }
+        Ok(())
+    }
+
+    /// Average price of token0 in token1 at which `wallet` received liquidity
+    /// in `pair`, from the `Mint` logs of the transactions that minted LP
+    /// tokens to it. LP tokens received by transfer are not counted.
+    async fn v2_entry_price(
+        &self,
+        pair: &UNISWAP_V2_POOL<HttpSigner>,
+        wallet: Address,
+        token0: Address,
+        token1: Address,
+        from_block: u64,
+    ) -> Result<Option<f64>, Box<dyn std::error::Error>> {
+        let first_mint = self
+            .explorer_client
+            .get_erc20_token_transfer_events(
+                TokenQueryOption::ByAddressAndContract(wallet, pair.address()),
+                Some(TxListParams::new(0, 99999999, 1, 1000, Sort::Asc)),
+            )
+            .await
+            .ok()
+            .and_then(|transfers| {
+                transfers
+                    .iter()
+                    .filter(|transfer| transfer.from.is_zero() && transfer.to == Some(wallet))
+                    .find_map(|transfer| transfer.block_number.as_number())
+            });
+        let start = first_mint.map_or(from_block, |block| block.as_u64().max(from_block));
+        let filter = Filter::new()
+            .address(pair.address())
+            .topic0(V2TransferFilter::signature())
+            .topic1(H256::zero())
+            .topic2(H256::from(wallet));
+        let mut tx_hashes: Vec<H256> = self
+            .get_logs_chunked(&filter, start)
+            .await?
+            .into_iter()
+            .filter_map(|log| log.transaction_hash)
+            .collect();
+        tx_hashes.dedup();
+
+        let (mut total0, mut total1) = (U256::zero(), U256::zero());
+        for tx_hash in tx_hashes {
+            let receipt = self
+                .config
+                .http
+                .get_transaction_receipt(tx_hash)
+                .await?
+                .ok_or_else(|| format!("No receipt for {:?}", tx_hash))?;
+            for log in receipt.logs {
+                if log.address != pair.address() {
+                    continue;
+                }
+                if let Ok(mint) = ethers::contract::parse_log::<V2MintFilter>(log) {
+                    total0 += mint.amount_0;
+                    total1 += mint.amount_1;
+                }
+            }
+        }
+        if total0.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.token_units(token1, total1).await? / self.token_units(token0, total0).await?,
+        ))
+    }
+
+    async fn print_v3_positions(
+        &self,
+        manager: &UNISWAP_V3_POSITION_MANAGER<HttpSigner>,
+        wallet: Address,
+        from_block: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let factory = self.uniswap_v3_factory()?;
+        let count = manager.balance_of(wallet).call().await?;
+        println!("Uniswap V3 positions:");
+        if count.is_zero() {
+            println!("  No position");
+            return Ok(());
+        }
+
+        // Blocks where the positions were minted to the wallet, positions
+        // received by transfer are read from `from_block`
+        let mut mint_blocks = HashMap::new();
+        if let Ok(transfers) = self
+            .explorer_client
+            .get_erc721_token_transfer_events(
+                TokenQueryOption::ByAddressAndContract(wallet, manager.address()),
+                Some(TxListParams::new(0, 99999999, 1, 1000, Sort::Asc)),
+            )
+            .await
+        {
+            for transfer in transfers.iter().filter(|transfer| transfer.from.is_zero()) {
+                if let (Ok(token_id), Some(block)) = (
+                    U256::from_dec_str(&transfer.token_id),
+                    transfer.block_number.as_number(),
+                ) {
+                    mint_blocks.entry(token_id).or_insert(block.as_u64());
+                }
+            }
+        }
+        for index in 0..count.as_u64() {
+            let token_id = manager
+                .token_of_owner_by_index(wallet, U256::from(index))
+                .call()
+                .await?;
+            let position = uniswap::position(manager, token_id).await?;
+            let symbol0 = self.token_symbol(position.token0).await;
+            let symbol1 = self.token_symbol(position.token1).await;
+            let pool_address =
+                uniswap::get_pool(&factory, position.token0, position.token1, position.fee)
+                    .await?
+                    .ok_or_else(|| format!("Position #{} has no pool", token_id))?;
+            let pool = UNISWAP_V3_POOL::new(pool_address, self.config.http.clone());
+            let state = uniswap::pool_state(&pool).await?;
+
+            let decimals0 = self.token_decimals(position.token0).await?;
+            let decimals1 = self.token_decimals(position.token1).await?;
+            let status = if position.liquidity == 0 {
+                "closed"
+            } else if position.in_range(state.tick) {
+                "in range"
+            } else {
+                "out of range"
+            };
+            println!(
+                "#{} {}/{} {}: {} to {} {} per {}, {}",
+                token_id,
+                symbol0,
+                symbol1,
+                uniswap::format_fee(position.fee),
+                tick_math::tick_to_price(position.tick_lower, decimals0, decimals1),
+                tick_math::tick_to_price(position.tick_upper, decimals0, decimals1),
+                symbol1,
+                symbol0,
+                status
+            );
+
+            let (amount0, amount1) = position.amounts(state.sqrt_price_x96)?;
+            let (fees0, fees1) = position.uncollected_fees(&pool, state.tick).await?;
+            println!(
+                "  Underlying: {} and {}",
+                self.format_token_amount(position.token0, amount0).await?,
+                self.format_token_amount(position.token1, amount1).await?
+            );
+            println!(
+                "  Uncollected fees: {} and {}",
+                self.format_token_amount(position.token0, fees0).await?,
+                self.format_token_amount(position.token1, fees1).await?
+            );
+            if position.liquidity == 0 {
+                continue;
+            }
+
+            let price = tick_math::sqrt_price_to_price(state.sqrt_price_x96, decimals0, decimals1);
+            let value = self.token_units(position.token0, amount0).await? * price
+                + self.token_units(position.token1, amount1).await?;
+            let start = mint_blocks
+                .get(&token_id)
+                .map_or(from_block, |block| (*block).max(from_block));
+            let hold = match self.v3_deposit_basis(manager, token_id, start).await {
+                Ok(Some((basis0, basis1))) => Some(
+                    self.token_units(position.token0, basis0).await? * price
+                        + self.token_units(position.token1, basis1).await?,
+                ),
+                Ok(None) => None,
+                Err(e) => {
+                    println!("  Could not read the deposit history: {}", e);
+                    None
+                }
+            };
+            Self::print_position_value(value, hold, &symbol1);
+        }
+        Ok(())
+    }
+
+    /// Token amounts deposited in a V3 position that are still in it, from
+    /// its liquidity events. Withdrawals reduce both amounts in proportion
+    /// to the share of liquidity removed. Events are read from `from_block`,
+    /// which must not be after the position was minted.
+    async fn v3_deposit_basis(
+        &self,
+        manager: &UNISWAP_V3_POSITION_MANAGER<HttpSigner>,
+        token_id: U256,
+        from_block: u64,
+    ) -> Result<Option<(U256, U256)>, Box<dyn std::error::Error>> {
+        let filter = Filter::new()
+            .address(manager.address())
+            .topic0(vec![
+                IncreaseLiquidityFilter::signature(),
+                DecreaseLiquidityFilter::signature(),
+            ])
+            .topic1(H256::from_uint(&token_id));
+        let logs = self.get_logs_chunked(&filter, from_block).await?;
+
+        let mut events = Vec::new();
+        for log in logs {
+            let position = (log.block_number, log.log_index);
+            if let Ok(event) = ethers::contract::parse_log::<IncreaseLiquidityFilter>(log.clone()) {
+                events.push((
+                    position,
+                    true,
+                    event.liquidity,
+                    event.amount_0,
+                    event.amount_1,
+                ));
+            } else {
+                let event = ethers::contract::parse_log::<DecreaseLiquidityFilter>(log)?;
+                events.push((
+                    position,
+                    false,
+                    event.liquidity,
+                    event.amount_0,
+                    event.amount_1,
+                ));
+            }
+        }
+        if events.is_empty() {
+            return Ok(None);
+        }
+        events.sort_by_key(|(position, ..)| *position);
+
+        let (mut liquidity, mut basis0, mut basis1) = (U256::zero(), U256::zero(), U256::zero());
+        for (_, increase, delta, amount0, amount1) in events {
+            let delta = U256::from(delta);
+            if increase {
+                liquidity += delta;
+                basis0 += amount0;
+                basis1 += amount1;
+            } else if !liquidity.is_zero() {
+                let remaining = liquidity.saturating_sub(delta);
+                basis0 = basis0 * remaining / liquidity;
+                basis1 = basis1 * remaining / liquidity;
+                liquidity = remaining;
+            }
+        }
+        Ok(Some((basis0, basis1)))
+    }
+
+    /// The V3 pool of two tokens for `fee`, or their V2 pair without a fee.
+    pub async fn find_pool(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        fee: Option<u32>,
+    ) -> Result<Address, Box<dyn std::error::Error>> {
+        let pool = match fee {
+            Some(fee) => {
+                uniswap::get_pool(&self.uniswap_v3_factory()?, token_a, token_b, fee).await?
+            }
+            None => Some(
+                self.uniswap_v2_factory()?
+                    .get_pair(token_a, token_b)
+                    .call()
+                    .await?,
+            )
+            .filter(|pair| !pair.is_zero()),
+        };
+        pool.ok_or_else(|| {
+            let version = match fee {
+                Some(fee) => format!("{} Uniswap V3 pool", uniswap::format_fee(fee)),
+                None => "Uniswap V2 pair".to_string(),
+            };
+            format!(
+                "No {} for {:?} and {:?} on {}",
+                version, token_a, token_b, self.network
+            )
+            .into()
+        })
+    }
+
+    /// Prints the state of a V2 pair or V3 pool, telling them apart by the
+    /// V3 `fee()` getter.
+    pub async fn inspect_pool(&self, pool: Address) -> Result<(), Box<dyn std::error::Error>> {
+        let v3_pool = UNISWAP_V3_POOL::new(pool, self.config.http.clone());
+        if let Ok(fee) = v3_pool.fee().call().await {
+            return self.inspect_v3_pool(&v3_pool, fee).await;
+        }
+        let pair = UNISWAP_V2_POOL::new(pool, self.config.http.clone());
+        if pair.get_reserves().call().await.is_err() {
+            return Err(format!("{:?} is not a Uniswap V2 pair or V3 pool", pool).into());
+        }
+        self.inspect_v2_pair(&pair).await
+    }
+
+    /// Prints the price of token0 in token1 and its inverse.
+    async fn print_pool_price(&self, token0: Address, token1: Address, price: f64) {
+        let symbol0 = self.token_symbol(token0).await;
+        let symbol1 = self.token_symbol(token1).await;
+        println!(
+            "Price: 1 {} = {} {}, 1 {} = {} {}",
+            symbol0,
+            price,
+            symbol1,
+            symbol1,
+            1.0 / price,
+            symbol0
+        );
+    }
+
+    async fn inspect_v2_pair(
+        &self,
+        pair: &UNISWAP_V2_POOL<HttpSigner>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let token0 = pair.token_0().call().await?;
+        let token1 = pair.token_1().call().await?;
+        let factory = pair.factory().call().await?;
+        let (reserve_0, reserve_1, last_update) = pair.get_reserves().call().await?;
+        let total_supply = pair.total_supply().call().await?;
+        let k_last = pair.k_last().call().await?;
+
+        println!(
+            "Uniswap V2 pair {:?} of factory {:?}",
+            pair.address(),
+            factory
+        );
+        println!("Token0: {} ({:?})", self.token_symbol(token0).await, token0);
+        println!("Token1: {} ({:?})", self.token_symbol(token1).await, token1);
+        println!(
+            "Reserves: {} and {}, last updated at {}",
+            self.format_token_amount(token0, U256::from(reserve_0))
+                .await?,
+            self.format_token_amount(token1, U256::from(reserve_1))
+                .await?,
+            last_update
+        );
+        if reserve_0 == 0 || reserve_1 == 0 {
+            println!("The pair has no liquidity");
+        } else {
+            let price = self.token_units(token1, U256::from(reserve_1)).await?
+                / self.token_units(token0, U256::from(reserve_0)).await?;
+            self.print_pool_price(token0, token1, price).await;
+        }
+        println!(
+            "LP supply: {}",
+            ethers::utils::format_units(total_supply, 18)?
+        );
+
+        let fee_to = UNISWAP_V2_FACTORY::new(factory, self.config.http.clone())
+            .fee_to()
+            .call()
+            .await?;
+        if fee_to.is_zero() {
+            println!("Protocol fee: off, kLast {}", k_last);
+        } else {
+            let pending =
+                uniswap::v2_pending_protocol_fee(reserve_0, reserve_1, k_last, total_supply);
+            println!(
+                "Protocol fee: on, paid to {:?}, kLast {}, {} LP tokens accrued since",
+                fee_to,
+                k_last,
+                ethers::utils::format_units(pending, 18)?
+            );
+        }
+        Ok(())
+    }
+
+    async fn inspect_v3_pool(
+        &self,
+        pool: &UNISWAP_V3_POOL<HttpSigner>,
+        fee: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let token0 = pool.token_0().call().await?;
+        let token1 = pool.token_1().call().await?;
+        let factory = pool.factory().call().await?;
+        let (_, _, observation_index, cardinality, cardinality_next, fee_protocol, _) =
+            pool.slot_0().call().await?;
+        let state = uniswap::pool_state(pool).await?;
+        let (protocol_fees0, protocol_fees1) = pool.protocol_fees().call().await?;
+        let symbol0 = self.token_symbol(token0).await;
+        let symbol1 = self.token_symbol(token1).await;
+        let decimals0 = self.token_decimals(token0).await?;
+        let decimals1 = self.token_decimals(token1).await?;
+
+        println!(
+            "Uniswap V3 pool {:?} of factory {:?}, fee {}, tick spacing {}",
+            pool.address(),
+            factory,
+            uniswap::format_fee(fee),
+            state.tick_spacing
+        );
+        println!("Token0: {} ({:?})", symbol0, token0);
+        println!("Token1: {} ({:?})", symbol1, token1);
+        println!(
+            "Slot0: sqrtPriceX96 {}, tick {}, observation {} of {} (next cardinality {})",
+            state.sqrt_price_x96, state.tick, observation_index, cardinality, cardinality_next
+        );
+        if state.sqrt_price_x96.is_zero() {
+            println!("The pool is not initialized");
+            return Ok(());
+        }
+        self.print_pool_price(
+            token0,
+            token1,
+            tick_math::sqrt_price_to_price(state.sqrt_price_x96, decimals0, decimals1),
+        )
+        .await;
+        println!("In-range liquidity: {}", state.liquidity);
+        // Each half of feeProtocol is the denominator of the swap fee share
+        // taken by the protocol, 0 when disabled
+        let share = |denominator: u8| match denominator {
+            0 => "off".to_string(),
+            denominator => format!("1/{} of swap fees", denominator),
+        };
+        println!(
+            "Protocol fee: {} on {}, {} on {}, accrued {} and {}",
+            share(fee_protocol % 16),
+            symbol0,
+            share(fee_protocol >> 4),
+            symbol1,
+            self.format_token_amount(token0, U256::from(protocol_fees0))
+                .await?,
+            self.format_token_amount(token1, U256::from(protocol_fees1))
+                .await?
+        );
+
+        // A 5% price move spans about 490 ticks
+        let span = 600.max(state.tick_spacing * 10);
+        let ticks = uniswap::initialized_ticks(pool, state.tick, state.tick_spacing, span).await?;
+        let below = ticks.iter().filter(|t| t.tick <= state.tick).rev().take(5);
+        let above = ticks.iter().filter(|t| t.tick > state.tick).take(5);
+        let mut nearest: Vec<_> = below.chain(above).collect();
+        nearest.sort_by_key(|t| t.tick);
+        println!("Initialized ticks near the price:");
+        if nearest.is_empty() {
+            println!("  None within {} ticks", span);
+        }
+        for t in nearest {
+            println!(
+                "  {:>8} ({} {} per {}): net liquidity {}, gross {}",
+                t.tick,
+                tick_math::tick_to_price(t.tick, decimals0, decimals1),
+                symbol1,
+                symbol0,
+                t.liquidity_net,
+                t.liquidity_gross
+            );
+        }
+
+        println!("Depth:");
+        for change in [-0.05, -0.01, 0.01, 0.05] {
+            let (amount_in, amount_out) = uniswap::depth(&state, &ticks, fee, change)?;
+            let (token_in, token_out) = if change > 0.0 {
+                (token1, token0)
+            } else {
+                (token0, token1)
+            };
+            println!(
+                "  {:>+3.0}%: sell {} for {}",
+                change * 100.0,
+                self.format_token_amount(token_in, amount_in).await?,
+                self.format_token_amount(token_out, amount_out).await?
+            );
+        }
+        Ok(())
+    }
+
+    /// Average seconds between the last 1000 blocks.
+    async fn average_block_time(&self) -> Result<f64, Box<dyn std::error::Error>> {
+        const SAMPLE: u64 = 1000;
+        let latest = self
+            .config
+            .http
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or("Latest block not found")?;
+        let latest_number = latest.number.ok_or("Latest block has no number")?.as_u64();
+        let earlier = self
+            .config
+            .http
+            .get_block(latest_number.saturating_sub(SAMPLE))
+            .await?
+            .ok_or("Block not found")?;
+        let blocks = latest_number - earlier.number.unwrap_or_default().as_u64();
+        if blocks == 0 {
+            return Ok(1.0);
+        }
+        Ok((latest.timestamp - earlier.timestamp).as_u64() as f64 / blocks as f64)
+    }
+
+    /// Prints the time-weighted average tick and price of a V3 pool over
+    /// `window` seconds and how far the spot price is from it. When the
+    /// pool's observations are too short for the window, `increase_cardinality`
+    /// sends a transaction to store more of them.
+    pub async fn twap(
+        &self,
+        pool: Address,
+        window: u32,
+        increase_cardinality: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pool = UNISWAP_V3_POOL::new(pool, self.config.http.clone());
+        let token0 = pool
+            .token_0()
+            .call()
+            .await
+            .map_err(|_| format!("{:?} is not a Uniswap V3 pool", pool.address()))?;
+        let token1 = pool.token_1().call().await?;
+        let (sqrt_price_x96, tick, observation_index, cardinality, cardinality_next, ..) =
+            pool.slot_0().call().await?;
+        if cardinality == 0 {
+            return Err(format!("Pool {:?} is not initialized", pool.address()).into());
+        }
+
+        // The slot after the latest observation holds the oldest one, unless
+        // the ring buffer has not wrapped around yet
+        let (mut oldest, _, _, initialized) = pool
+            .observations(U256::from((observation_index + 1) % cardinality))
+            .call()
+            .await?;
+        if !initialized {
+            oldest = pool.observations(U256::zero()).call().await?.0;
+        }
+        let history = utils::unix_now().saturating_sub(oldest as u64);
+        println!(
+            "Pool {:?}: {} observations (next cardinality {}), the oldest from {}s ago",
+            pool.address(),
+            cardinality,
+            cardinality_next,
+            history
+        );
+
+        if history < window as u64 {
+            // One observation is written per block with a swap at most
+            let block_time = self.average_block_time().await?;
+            let needed =
+                ((window as f64 / block_time).ceil() as u64 + 1).min(u16::MAX as u64) as u16;
+            if !increase_cardinality {
+                return Err(format!(
+                    "The observations only cover the last {}s, pass --increase-cardinality to store {} of them",
+                    history, needed
+                )
+                .into());
+            }
+            if cardinality_next >= needed {
+                println!(
+                    "The cardinality is already raised to {}, the observations will cover {}s as swaps happen",
+                    cardinality_next, window
+                );
+                return Ok(());
+            }
+            let tx = pool.increase_observation_cardinality_next(needed);
+            let pending_tx = tx.send().await?;
+            println!(
+                "Increase observation cardinality transaction sent: {:?}",
+                pending_tx.tx_hash()
+            );
+            let receipt = pending_tx.await?;
+            println!(
+                "Increase observation cardinality transaction receipt: {:?}",
+                receipt
+            );
+            println!(
+                "The observations will cover {}s once {} more have been written, one per block with a swap",
+                window,
+                needed - cardinality
+            );
+            return Ok(());
+        }
+
+        let twap_tick = uniswap::twap_tick(&pool, window).await?;
+        let decimals0 = self.token_decimals(token0).await?;
+        let decimals1 = self.token_decimals(token1).await?;
+        let symbol0 = self.token_symbol(token0).await;
+        let symbol1 = self.token_symbol(token1).await;
+        let twap_price = tick_math::tick_to_price(twap_tick, decimals0, decimals1);
+        let spot_price = tick_math::sqrt_price_to_price(sqrt_price_x96, decimals0, decimals1);
+        println!(
+            "TWAP over {}s: tick {}, 1 {} = {} {}, 1 {} = {} {}",
+            window,
+            twap_tick,
+            symbol0,
+            twap_price,
+            symbol1,
+            symbol1,
+            1.0 / twap_price,
+            symbol0
+        );
+        println!(
+            "Spot: tick {}, 1 {} = {} {}, {:.1} bps from the TWAP",
+            tick,
+            symbol0,
+            spot_price,
+            symbol1,
+            uniswap::deviation_bps(spot_price, twap_price)
+        );
+        Ok(())
+    }
+
+    /// Refuses a quoted swap when the spot price of a hop deviates from its
+    /// TWAP by more than the guard allows. V2 pairs have no usable oracle, so
+    /// their spot price is compared with the TWAP of the pair's deepest V3
+    /// pool.
+    async fn check_twap_deviation(
+        &self,
+        quote: &Quote,
+        guard: TwapGuard,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for hop in &quote.hops {
+            let oracle = match quote.version {
+                uniswap::Version::V3 => hop.pool,
+                uniswap::Version::V2 => uniswap::deepest_pool(
+                    &self.uniswap_v3_factory()?,
+                    self.config.http.clone(),
+                    hop.token_in,
+                    hop.token_out,
+                )
+                .await?
+                .map(|(_, pool, _)| pool)
+                .ok_or_else(|| {
+                    format!(
+                        "No Uniswap V3 pool for {:?} and {:?} to read a TWAP from",
+                        hop.token_in, hop.token_out
+                    )
+                })?,
+            };
+            let tick = uniswap::twap_tick(
+                &UNISWAP_V3_POOL::new(oracle, self.config.http.clone()),
+                guard.window,
+            )
+            .await?;
+            let deviation = uniswap::deviation_bps(
+                hop.mid_price,
+                uniswap::tick_price(hop.token_in, hop.token_out, tick),
+            );
+            println!(
+                "Spot price of {:?} is {:.1} bps from the {}s TWAP of {:?}",
+                hop.pool, deviation, guard.window, oracle
+            );
+            if deviation > guard.max_deviation_bps as f64 {
+                return Err(format!(
+                    "Spot price of {:?} deviates {:.1} bps from its TWAP, above the {} bps limit, the pool may be manipulated",
+                    hop.pool,
+                    deviation,
+                    guard.max_deviation_bps
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Value of `amount` wei in `token`, quoted through the deepest WETH
+    /// pool on Uniswap V3.
+    async fn native_value_in(
+        &self,
+        token: Address,
+        amount: U256,
+    ) -> Result<U256, Box<dyn std::error::Error>> {
+        let weth = addressbook::contract_address("weth", self.config.chain)
+            .ok_or_else(|| format!("WETH not deployed on {}", self.network))?;
+        if token == weth || amount.is_zero() {
+            return Ok(amount);
+        }
+        let (fee, _, _) = uniswap::deepest_pool(
+            &self.uniswap_v3_factory()?,
+            self.config.http.clone(),
+            weth,
+            token,
+        )
+        .await?
+        .ok_or_else(|| format!("No Uniswap V3 pool to price gas in {:?}", token))?;
+        let quote = self
+            .get_uniswap_quote(
+                &Route::V3(SwapPath::single(weth, token, fee)),
+                SwapKind::ExactInput,
+                amount,
+            )
+            .await?;
+        Ok(quote.amount_out)
+    }
+
+    /// Quotes selling `amount` of `token_in` on the V2 pair, every V3 fee
+    /// tier and LI.FI, ranks the quotes by output net of gas, and executes
+    /// the best one unless `quote_only` is set.
+    pub async fn best_swap(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount: String,
+        slippage_bps: u32,
+        recipient: Address,
+        quote_only: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_token_whitelisted(token_in, "Input token")?;
+        self.ensure_token_whitelisted(token_out, "Output token")?;
+        // Executing checks the recipient in the chosen swap path
+        if quote_only {
+            self.ensure_recipient_whitelisted(recipient)?;
+        }
+
+        let amount_in = self.parse_token_amount(token_in, &amount).await?;
+        let gas_price = self.config.http.get_gas_price().await?;
+        let mut candidates = Vec::new();
+
+        let tokens = vec![token_in, token_out];
+        match self
+            .get_uniswap_quote(&Route::V2(tokens.clone()), SwapKind::ExactInput, amount_in)
+            .await
+        {
+            Ok(quote) => candidates.push(SwapCandidate {
+                label: uniswap::Version::V2.to_string(),
+                venue: SwapVenue::UniswapV2(tokens),
+                amount_out: quote.amount_out,
+                gas_cost: gas_price * uniswap::V2_SWAP_GAS,
+            }),
+            Err(e) => println!("{}: no quote ({})", uniswap::Version::V2, e),
+        }
+
+        for fee in uniswap::FEE_TIERS {
+            let label = format!("{} {}", uniswap::Version::V3, uniswap::format_fee(fee));
+            let path = SwapPath::single(token_in, token_out, fee);
+            match self
+                .get_uniswap_quote(&Route::V3(path.clone()), SwapKind::ExactInput, amount_in)
+                .await
+            {
+                Ok(quote) => candidates.push(SwapCandidate {
+                    label,
+                    venue: SwapVenue::UniswapV3(path),
+                    amount_out: quote.amount_out,
+                    gas_cost: gas_price
+                        * (quote.gas_estimate.unwrap_or_default() + uniswap::V3_SWAP_OVERHEAD_GAS),
+                }),
+                Err(e) => println!("{}: no quote ({})", label, e),
+            }
+        }
+
+        let request = QuoteRequest::new(
+            self.config.chain_id.to_string(),
+            self.config.chain_id.to_string(),
+            format!("{:?}", token_in),
+            format!("{:?}", token_out),
+            amount_in.to_string(),
+            format!("{:?}", self.config.http.address()),
+            format!("{:?}", recipient),
+        )
+        .with_slippage(slippage_bps as f64 / 10_000.0);
+        match LiFiBridge::new().request_quote(request).await {
+            Ok(quote) => {
+                let amount_out = U256::from_dec_str(&quote.estimate.to_amount)?;
+                let mut gas_cost = U256::zero();
+                for cost in &quote.estimate.gas_costs {
+                    gas_cost += U256::from_dec_str(&cost.amount)?;
+                }
+                candidates.push(SwapCandidate {
+                    label: format!("LI.FI via {}", quote.tool),
+                    venue: SwapVenue::LiFi(Box::new(quote)),
+                    amount_out,
+                    gas_cost,
+                });
+            }
+            Err(e) => println!("LI.FI: no quote ({})", e),
+        }
+
+        if candidates.is_empty() {
+            return Err("No venue could quote the swap".into());
+        }
+
+        // Gas is paid in the native asset, price it in the output token with
+        // a single quote so every candidate is charged at the same rate
+        let one_ether = U256::exp10(18);
+        let gas_rate = match self.native_value_in(token_out, one_ether).await {
+            Ok(rate) => rate,
+            Err(e) => {
+                println!(
+                    "Warning: could not price gas in the output token, ranking by gross output ({})",
+                    e
+                );
+                U256::zero()
+            }
+        };
+        let net = |candidate: &SwapCandidate| {
+            candidate
+                .amount_out
+                .saturating_sub(candidate.gas_cost * gas_rate / one_ether)
+        };
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(net(candidate)));
+
+        println!(
+            "Selling {}:",
+            self.format_token_amount(token_in, amount_in).await?
+        );
+        for (rank, candidate) in candidates.iter().enumerate() {
+            println!(
+                "{}. {}: {} out, gas {}, net {}",
+                rank + 1,
+                candidate.label,
+                self.format_token_amount(token_out, candidate.amount_out)
+                    .await?,
+                self.format_token_amount(token_out, candidate.gas_cost * gas_rate / one_ether)
+                    .await?,
+                self.format_token_amount(token_out, net(candidate)).await?
+            );
+        }
+        if quote_only {
+            return Ok(());
+        }
+
+        let best = candidates.remove(0);
+        println!("Executing through {}", best.label);
+        match best.venue {
+            SwapVenue::UniswapV2(path) => {
+                self.swap_tokens_uniswap_v2(
+                    path,
+                    SwapKind::ExactInput,
+                    amount,
+                    false,
+                    false,
+                    false,
+                    slippage_bps,
+                    None,
+                    recipient,
+                )
+                .await
+            }
+            SwapVenue::UniswapV3(path) => {
+                self.swap_tokens_uniswap_v3(
+                    path,
+                    SwapKind::ExactInput,
+                    amount,
+                    None,
+                    slippage_bps,
+                    None,
+                    recipient,
+                )
+                .await
+            }
+            SwapVenue::LiFi(quote) => {
+                self.execute_lifi_quote(
+                    &quote,
+                    token_in,
+                    token_out,
+                    amount_in,
+                    slippage_bps,
+                    recipient,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Sends the transaction of a same-chain LI.FI quote, approving its
+    /// contract for `amount` of `token_in` first. The quote comes from the
+    /// LI.FI API, so its contract must be the LI.FI diamond pinned in the
+    /// addressbook, and its recipient, input amount and minimum output must
+    /// match the request.
+    async fn execute_lifi_quote(
+        &self,
+        quote: &LifiQuote,
+        token_in: Address,
+        token_out: Address,
+        amount: U256,
+        slippage_bps: u32,
+        recipient: Address,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_recipient_whitelisted(recipient)?;
+        self.ensure_recipient_not_poisoned(recipient).await?;
+        let quoted_in = Address::from_str(&quote.action.from_token.address)?;
+        let quoted_out = Address::from_str(&quote.action.to_token.address)?;
+        if quoted_in != token_in || quoted_out != token_out {
+            return Err(format!(
+                "The LI.FI quote swaps {:?} for {:?}, not the requested tokens",
+                quoted_in, quoted_out
+            )
+            .into());
+        }
+        let request = quote
+            .transaction_request
+            .as_ref()
+            .ok_or("The LI.FI quote has no transaction to send")?;
+        let to = Address::from_str(&request.to)?;
+        let approval_address = Address::from_str(&quote.estimate.approval_address)?;
+        if to != approval_address {
+            return Err(format!(
+                "The LI.FI transaction targets {:?} but asks to approve {:?}",
+                to, approval_address
+            )
+            .into());
+        }
+        let diamond = addressbook::contract_address("lifiDiamond", self.config.chain).ok_or(
+            format!("No LI.FI diamond in the addressbook for {}", self.network),
+        )?;
+        if to != diamond {
+            return Err(format!(
+                "The LI.FI transaction targets {:?}, not the LI.FI diamond {:?}",
+                to, diamond
+            )
+            .into());
+        }
+        let quoted_recipient = quote
+            .action
+            .to_address
+            .as_deref()
+            .ok_or("The LI.FI quote has no recipient")?;
+        let quoted_recipient = Address::from_str(quoted_recipient)?;
+        if quoted_recipient != recipient {
+            return Err(format!(
+                "The LI.FI quote pays {:?}, not the recipient {:?}",
+                quoted_recipient, recipient
+            )
+            .into());
+        }
+        let quoted_amount = U256::from_dec_str(&quote.action.from_amount)?;
+        if quoted_amount != amount {
+            return Err(format!("The LI.FI quote sells {}, not {}", quoted_amount, amount).into());
+        }
+        let amount_out_min = U256::from_dec_str(&quote.estimate.to_amount_min)?;
+        let expected_min =
+            uniswap::minimum_out(U256::from_dec_str(&quote.estimate.to_amount)?, slippage_bps);
+        if amount_out_min < expected_min {
+            return Err(format!(
+                "The LI.FI minimum output {} is below {} allowed by a slippage of {} bps",
+                self.format_token_amount(token_out, amount_out_min).await?,
+                self.format_token_amount(token_out, expected_min).await?,
+                slippage_bps
+            )
+            .into());
+        }
+        println!(
+            "LI.FI minimum output: {}",
+            self.format_token_amount(token_out, amount_out_min).await?
+        );
+        self.enforce_spending_policy(Some(token_in), recipient, amount)?;
+
+        self.ensure_allowance(token_in, to, amount).await?;
+
+        let hex = |value: &str| U256::from_str_radix(value.trim_start_matches("0x"), 16);
+        let mut tx = TransactionRequest::new()
+            .to(to)
+            .data(Bytes::from_str(&request.data)?)
+            .value(hex(&request.value)?);
+        if let Some(gas_limit) = &request.gas_limit {
+            tx = tx.gas(hex(gas_limit)?);
+        }
+        let pending_tx = self.config.http.send_transaction(tx, None).await?;
+        println!("Swap transaction sent: {:?}", pending_tx.tx_hash());
+        self.record_spend(
+            "swap_lifi",
+            pending_tx.tx_hash(),
+            Some(token_in),
+            recipient,
+            amount,
+        )?;
+
+        let receipt = pending_tx
+            .await?
+            .ok_or("Swap transaction was dropped from the mempool")?;
+        println!("Swap transaction receipt: {:?}", receipt);
+        if receipt.status != Some(U64::one()) {
+            return Err(format!("Swap transaction {:?} reverted", receipt.transaction_hash).into());
+        }
+        Ok(())
+    }
+
+    /// Addressbook or whitelisted wallet name of `address`.
+    fn known_label(&self, address: Address) -> Option<String> {
+        addressbook::contract_name(address, self.config.chain)
+            .or_else(|| self.whitelist.wallet_name(address, self.config.chain_id))
+            .map(str::to_string)
+    }
+
+    /// Logs matching `filter` from `from_block` to the latest block. When the
+    /// node refuses to scan the range at once, it is scanned in chunks of
+    /// `LOG_CHUNK_BLOCKS`.
+    async fn get_logs_chunked(
+        &self,
+        filter: &Filter,
+        from_block: u64,
+    ) -> Result<Vec<Log>, Box<dyn std::error::Error>> {
+        let latest = self.config.http.get_block_number().await?.as_u64();
+        match self
+            .config
+            .http
+            .get_logs(&filter.clone().from_block(from_block).to_block(latest))
+            .await
+        {
+            Ok(logs) => Ok(logs),
+            Err(e) => {
+                println!(
+                    "Could not scan blocks {} to {} at once ({}), scanning {} blocks at a time",
+                    from_block, latest, e, LOG_CHUNK_BLOCKS
+                );
+                let mut logs = Vec::new();
+                let mut start = from_block;
+                while start <= latest {
+                    let end = (start + LOG_CHUNK_BLOCKS - 1).min(latest);
+                    let chunk = filter.clone().from_block(start).to_block(end);
+                    logs.extend(self.config.http.get_logs(&chunk).await?);
+                    start = end + 1;
+                }
+                Ok(logs)
+            }
+        }
+    }
+
+    /// Token and spender pairs of the `Approval` logs emitted for `owner`
+    /// since `from_block`.
+    async fn approval_pairs(
+        &self,
+        owner: Address,
+        from_block: u64,
+    ) -> Result<Vec<(Address, Address)>, Box<dyn std::error::Error>> {
+        let filter = Filter::new()
+            .topic0(approval::approval_topic())
+            .topic1(H256::from(owner));
+        let logs = self.get_logs_chunked(&filter, from_block).await?;
+
+        // ERC721 approvals have the same signature but also index the token id
+        let mut pairs: Vec<(Address, Address)> = logs
+            .iter()
+            .filter(|log| log.topics.len() == 3)
+            .map(|log| (log.address, Address::from(log.topics[2])))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        Ok(pairs)
+    }
+
+    /// Lists the open allowances of each wallet, found from its `Approval`
+    /// logs and read back with `allowance()`. Unlimited allowances to
+    /// spenders that are neither in the addressbook nor whitelisted are
+    /// flagged.
+    pub async fn allowances(
+        &self,
+        wallets: &[(Address, String)],
+        from_block: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut flagged = 0;
+        for (wallet, name) in wallets {
+            println!("Allowances granted by {} ({:?}):", name, wallet);
+            let mut found = 0;
+            for (token, spender) in self.approval_pairs(*wallet, from_block).await? {
+                // Contracts that are not ERC20 tokens may emit the same event
+                let Ok(allowance) = ERC20::new(token, self.config.http.clone())
+                    .allowance(*wallet, spender)
+                    .call()
+                    .await
+                else {
+                    continue;
+                };
+                if allowance.is_zero() {
+                    continue;
+                }
+                found += 1;
+
+                let label = self.known_label(spender);
+                let unlimited = approval::is_unlimited(allowance);
+                let amount = if unlimited {
+                    format!("unlimited {}", self.token_symbol(token).await)
+                } else {
+                    self.format_token_amount(token, allowance)
+                        .await
+                        .unwrap_or_else(|_| format!("{} units of {:?}", allowance, token))
+                };
+                let marker = if unlimited && label.is_none() {
+                    flagged += 1;
+                    "!"
+                } else {
+                    " "
+                };
+                println!(
+                    "{} {} ({:?}) to {:?} ({})",
+                    marker,
+                    amount,
+                    token,
+                    spender,
+                    label.as_deref().unwrap_or("unknown")
+                );
+            }
+            if found == 0 {
+                println!("  No open allowances");
+            }
+        }
+        if flagged > 0 {
+            println!(
+                "{} unlimited allowances to unknown spenders are marked with !, they can be revoked with `revoke --token <token> --spender <spender>`",
+                flagged
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets each token allowance of the signing wallet back to zero, after
+    /// listing them and asking for confirmation unless `yes` is set.
+    /// Allowances that are already zero are skipped.
+    pub async fn revoke(
+        &self,
+        approvals: &[(Address, Address)],
+        yes: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut pending = Vec::new();
+        for &(token, spender) in approvals {
+            let allowance = self.allowance(token, spender).await?;
+            let symbol = self.token_symbol(token).await;
+            let label = self
+                .known_label(spender)
+                .unwrap_or_else(|| "unknown".to_string());
+            if allowance.is_zero() {
+                println!("No {} allowance for {:?} ({})", symbol, spender, label);
+                continue;
+            }
+            let amount = if approval::is_unlimited(allowance) {
+                format!("unlimited {}", symbol)
+            } else {
+                self.format_token_amount(token, allowance).await?
+            };
+            println!("Revoke {} allowed to {:?} ({})", amount, spender, label);
+            pending.push((token, spender));
+        }
+        if pending.is_empty() {
+            println!("Nothing to revoke");
+            return Ok(());
+        }
+        let prompt = format!("Send {} revoke transactions?", pending.len());
+        if !yes && !utils::confirm(&prompt)? {
+            return Ok(());
+        }
+        for (token, spender) in pending {
+            let token_contract = USDT::new(token, self.config.http.clone());
+            self.send_approval(&token_contract, spender, U256::zero())
+                .await?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,69 @@
+use ethers::types::{Address, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use crate::config::get_journal_path;
+use crate::utils;
+
+/// A single outgoing transfer of value made by the tool. Transactions that
+/// spend several tokens (e.g. adding liquidity) are recorded as one entry per
+/// token, sharing the same transaction hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: u64,
+    pub chain_id: u64,
+    pub tx_hash: String,
+    pub kind: String,
+    pub from: String,
+    /// Token spent, `None` for the native asset.
+    pub token: Option<String>,
+    pub recipient: String,
+    pub amount: U256,
+}
+
+impl JournalEntry {
+    pub fn new(
+        chain_id: u64,
+        tx_hash: H256,
+        kind: &str,
+        from: Address,
+        token: Option<Address>,
+        recipient: Address,
+        amount: U256,
+    ) -> Self {
+        JournalEntry {
+            timestamp: utils::unix_now(),
+            chain_id,
+            tx_hash: format!("{:?}", tx_hash),
+            kind: kind.to_string(),
+            from: format!("{:?}", from),
+            token: token.map(|token| format!("{:?}", token)),
+            recipient: format!("{:?}", recipient),
+            amount,
+        }
+    }
+}
+
+/// Appends an entry to the journal file, one JSON object per line.
+pub fn record(entry: &JournalEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_journal_path())?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+pub fn load_entries() -> Result<Vec<JournalEntry>, Box<dyn std::error::Error>> {
+    let path = get_journal_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        entries.push(serde_json::from_str(line)?);
+    }
+    Ok(entries)
+}
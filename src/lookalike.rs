@@ -0,0 +1,90 @@
+use ethers::types::{Address, H256};
+
+use crate::whitelist::{WalletInfo, Whitelist};
+use std::str::FromStr;
+
+/// Leading and trailing hex characters that must match for two addresses to
+/// be considered lookalikes. Poisoning tools typically grind 4 to 6 of them,
+/// which is what wallets and explorers show in abbreviated addresses.
+pub const MIN_MATCHING_CHARS: usize = 4;
+
+/// How closely a different address mimics another one.
+#[derive(Debug, Clone, Copy)]
+pub struct Similarity {
+    pub prefix: usize,
+    pub suffix: usize,
+}
+
+/// Returns the matching prefix and suffix lengths if `a` and `b` are
+/// different addresses sharing at least `MIN_MATCHING_CHARS` hex characters
+/// at both ends.
+pub fn similarity(a: Address, b: Address) -> Option<Similarity> {
+    if a == b {
+        return None;
+    }
+    let a = hex_digits(a);
+    let b = hex_digits(b);
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (prefix >= MIN_MATCHING_CHARS && suffix >= MIN_MATCHING_CHARS)
+        .then_some(Similarity { prefix, suffix })
+}
+
+fn hex_digits(address: Address) -> Vec<u8> {
+    format!("{:x}", address).into_bytes()
+}
+
+/// Whitelisted wallets usable on `chain_id` that `address` is a lookalike of.
+pub fn find_lookalikes(
+    whitelist: &Whitelist,
+    chain_id: Option<u64>,
+    address: Address,
+) -> Vec<(&WalletInfo, Similarity)> {
+    let mut lookalikes: Vec<_> = whitelist
+        .get_wallet_addresses()
+        .values()
+        .filter(|info| chain_id.is_none_or(|id| info.applies_to(id)))
+        .filter_map(|info| {
+            let known = Address::from_str(&info.address).ok()?;
+            similarity(address, known).map(|similarity| (info, similarity))
+        })
+        .collect();
+    lookalikes.sort_by(|a, b| a.0.address.cmp(&b.0.address));
+    lookalikes.dedup_by(|a, b| a.0.address == b.0.address);
+    lookalikes
+}
+
+/// One line per lookalike, e.g. `0x1234…abcd (treasury), first 6 and last 4 characters match`.
+pub fn describe(lookalikes: &[(&WalletInfo, Similarity)]) -> String {
+    lookalikes
+        .iter()
+        .map(|(info, similarity)| {
+            format!(
+                "{} ({}), first {} and last {} characters match",
+                info.address,
+                info.name.as_deref().unwrap_or("unnamed"),
+                similarity.prefix,
+                similarity.suffix
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n  ")
+}
+
+/// A zero-value token transfer to or from a wallet whose counterparty mimics
+/// an address the wallet trusts.
+#[derive(Debug, Clone)]
+pub struct SuspiciousTransfer {
+    pub tx_hash: H256,
+    pub token_symbol: String,
+    pub counterparty: Address,
+    pub mimics: Address,
+    /// What the mimicked address is, e.g. a whitelist name or "this wallet".
+    pub mimics_label: String,
+    pub similarity: Similarity,
+}
@@ -1,22 +1,35 @@
 mod addressbook;
+mod approval;
 mod bindings;
 mod bridge;
 mod config;
 mod evm_interface;
+mod journal;
+mod lookalike;
+mod policy;
 mod resolver;
 mod signer_middleware;
+mod tick_math;
+mod uniswap;
 mod utils;
 mod whitelist;
+mod whitelist_io;
+use crate::approval::ApprovalMode;
 use crate::config::{
-    get_chain_config, get_chain_from_string, get_chain_id_from_string, get_whitelist_path,
+    get_addressbook_path, get_chain_config, get_chain_from_string, get_chain_id_from_string,
+    get_chain_scope_from_string, get_whitelist_path, ALL_CHAINS,
 };
+use crate::policy::{SpendingPolicy, NATIVE_TOKEN};
 use crate::resolver::Resolver;
+use crate::uniswap::{Route, SwapKind, SwapPath, Version};
 use crate::utils::{print_lifi_chains, print_lifi_connections, print_lifi_tokens, print_routes};
-use crate::whitelist::Whitelist;
-use clap::{Args, Parser, Subcommand};
+use crate::whitelist::{TokenType, Whitelist};
+use crate::whitelist_io::{ExportFormat, WhitelistExport};
+use clap::{ArgGroup, Args, Parser, Subcommand};
 use dotenv::dotenv;
-use ethers::types::{Address, Chain};
+use ethers::types::{Address, Chain, U256};
 use evm_interface::EVMInterface;
+use std::fs;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -24,6 +37,12 @@ use std::sync::Arc;
 struct Cli {
     #[clap(subcommand)]
     command: Command,
+    /// Resolve token symbols that are not whitelisted through the LI.FI token list
+    #[clap(long, global = true)]
+    use_token_list: bool,
+    /// How token approvals are granted before spending
+    #[clap(long, global = true, value_enum, default_value_t)]
+    approval: ApprovalMode,
 }
 
 #[derive(Subcommand)]
@@ -50,13 +69,30 @@ enum Command {
     GetTransferStatus(GetTransferStatusArgs),
     GetConnections(GetConnectionsArgs),
     GetTransactions(GetTransactionsArgs),
+    ScanAddressPoisoning(ScanAddressPoisoningArgs),
     AddWalletToWhitelist(AddWalletToWhitelistArgs),
     RemoveWalletFromWhitelist(RemoveWalletFromWhitelistArgs),
     AddTokenToWhitelist(AddTokenToWhitelistArgs),
     RemoveTokenFromWhitelist(RemoveTokenFromWhitelistArgs),
     ShowWhitelist,
+    SignWhitelist,
+    SetWhitelistDelay(SetWhitelistDelayArgs),
+    Whitelist(WhitelistArgs),
+    Addressbook(AddressbookArgs),
+    SetSpendingPolicy(SetSpendingPolicyArgs),
+    RemoveSpendingPolicy(RemoveSpendingPolicyArgs),
+    QuoteUniswap(QuoteUniswapArgs),
     SwapTokensUniswapV3(SwapTokensUniswapV3Args),
+    SwapTokensUniswapV2(SwapTokensUniswapV2Args),
     AddLiquidityUniswapV2(AddLiquidityUniswapV2Args),
+    RemoveLiquidityUniswapV2(RemoveLiquidityUniswapV2Args),
+    UniswapV3Position(UniswapV3PositionArgs),
+    LpPositions(LpPositionsArgs),
+    InspectPool(InspectPoolArgs),
+    Twap(TwapArgs),
+    BestSwap(BestSwapArgs),
+    Allowances(AllowancesArgs),
+    Revoke(RevokeArgs),
 }
 
 #[derive(Args)]
@@ -171,10 +207,13 @@ struct GetKnownTokensArgs {
 
 #[derive(Args)]
 struct RequestRoutesArgs {
-    #[clap(long)]
-    from_chain_id: u64,
-    #[clap(long)]
-    to_chain_id: u64,
+    /// Network name or chain id, optional if the token is chain-qualified
+    #[clap(long, alias = "from-chain-id")]
+    from_chain: Option<String>,
+    /// Network name or chain id, optional if the token is chain-qualified
+    #[clap(long, alias = "to-chain-id")]
+    to_chain: Option<String>,
+    /// Token to bridge, e.g. usdc@arbitrum or 42161:0xaf88...
     #[clap(long)]
     from_token_address: String,
     #[clap(long)]
@@ -185,10 +224,12 @@ struct RequestRoutesArgs {
 
 #[derive(Args)]
 struct RequestQuoteArgs {
+    /// Network name or chain id, optional if the token is chain-qualified
     #[clap(long)]
-    from_chain: String,
+    from_chain: Option<String>,
+    /// Network name or chain id, optional if the token is chain-qualified
     #[clap(long)]
-    to_chain: String,
+    to_chain: Option<String>,
     #[clap(long)]
     from_token: String,
     #[clap(long)]
@@ -239,12 +280,23 @@ struct GetTransactionsArgs {
     blocks: Option<u64>,
 }
 
+#[derive(Args)]
+struct ScanAddressPoisoningArgs {
+    #[clap(long)]
+    wallet: String,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
 #[derive(Args)]
 struct AddWalletToWhitelistArgs {
     #[clap(long)]
     address: String,
     #[clap(long)]
     name: Option<String>,
+    /// Network the wallet is valid on, or "all" for every chain
+    #[clap(long)]
+    chain: String,
 }
 
 #[derive(Args)]
@@ -253,6 +305,8 @@ struct RemoveWalletFromWhitelistArgs {
     address: String,
     #[clap(long)]
     name: Option<String>,
+    #[clap(long, default_value = ALL_CHAINS)]
+    chain: String,
 }
 
 #[derive(Args)]
@@ -263,6 +317,10 @@ struct AddTokenToWhitelistArgs {
     name: Option<String>,
     #[clap(long)]
     chain: String,
+    /// Fee-on-transfer and rebasing tokens cannot be detected and must be
+    /// declared. Proxies are detected automatically.
+    #[clap(long, value_enum)]
+    token_type: Option<TokenType>,
 }
 
 #[derive(Args)]
@@ -276,15 +334,249 @@ struct RemoveTokenFromWhitelistArgs {
 }
 
 #[derive(Args)]
+struct WhitelistArgs {
+    #[clap(subcommand)]
+    command: WhitelistCommand,
+}
+
+#[derive(Subcommand)]
+enum WhitelistCommand {
+    Export(WhitelistExportArgs),
+    Import(WhitelistImportArgs),
+    Refresh(WhitelistRefreshArgs),
+}
+
+#[derive(Args)]
+struct WhitelistExportArgs {
+    /// File to write to, printed to stdout if omitted
+    #[clap(long)]
+    output: Option<String>,
+    /// Defaults to the output file extension, or JSON
+    #[clap(long, value_enum)]
+    format: Option<ExportFormat>,
+}
+
+#[derive(Args)]
+struct WhitelistImportArgs {
+    #[clap(long)]
+    input: String,
+    /// Defaults to the input file extension
+    #[clap(long, value_enum)]
+    format: Option<ExportFormat>,
+    /// Do not read token metadata from the chain
+    #[clap(long)]
+    skip_onchain_check: bool,
+    /// Apply the changes without asking for confirmation
+    #[clap(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct WhitelistRefreshArgs {
+    /// Only check tokens on this network
+    #[clap(long)]
+    chain: Option<String>,
+    /// Record the current on-chain metadata for the flagged tokens
+    #[clap(long)]
+    accept: bool,
+}
+
+#[derive(Args)]
+struct AddressbookArgs {
+    #[clap(subcommand)]
+    command: AddressbookCommand,
+}
+
+#[derive(Subcommand)]
+enum AddressbookCommand {
+    Verify(AddressbookVerifyArgs),
+    Discover(AddressbookDiscoverArgs),
+    /// Sign the user addressbook with the admin key, applying its overrides of built-in entries
+    Sign,
+}
+
+#[derive(Args)]
+struct AddressbookDiscoverArgs {
+    #[clap(long)]
+    chain: String,
+    /// Uniswap V2 or V3 router to start from, may be repeated
+    #[clap(long)]
+    router: Vec<String>,
+    /// Expected WETH address, checked against the routers. Routers of other
+    /// chains deployed at the same address and wrapping it are used as seeds
+    #[clap(long)]
+    weth: Option<String>,
+    /// File to write the proposed entries to, defaults to addressbook.<chain>.proposed.json
+    #[clap(long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct AddressbookVerifyArgs {
+    /// Only verify entries on this network
+    #[clap(long)]
+    chain: Option<String>,
+}
+
+#[derive(Args)]
+struct SetWhitelistDelayArgs {
+    /// Hours before newly added wallets and tokens can be used, 0 to disable
+    #[clap(long)]
+    hours: u64,
+}
+
+#[derive(Args)]
+struct SetSpendingPolicyArgs {
+    /// Token address or name, or "eth" for the native asset
+    #[clap(long)]
+    token: String,
+    /// Restrict the policy to transfers to this wallet
+    #[clap(long)]
+    wallet: Option<String>,
+    /// Maximum amount per transaction, in base units
+    #[clap(long)]
+    max_per_tx: Option<String>,
+    /// Maximum amount over a rolling 24h window, in base units
+    #[clap(long)]
+    daily_cap: Option<String>,
+    /// Amount above which a transfer must be approved interactively, in base units
+    #[clap(long)]
+    approval_threshold: Option<String>,
+    #[clap(long)]
+    chain: String,
+}
+
+#[derive(Args)]
+struct RemoveSpendingPolicyArgs {
+    #[clap(long)]
+    token: String,
+    #[clap(long)]
+    wallet: Option<String>,
+    #[clap(long)]
+    chain: String,
+}
+
+#[derive(Args)]
+struct RouteArgs {
+    #[clap(long, required_unless_present = "path")]
+    token_in: Option<String>,
+    #[clap(long, required_unless_present = "path")]
+    token_out: Option<String>,
+    /// V3 pool fee in hundredths of a bip (100, 500, 3000, 10000), defaults
+    /// to the tier with the most liquidity
+    #[clap(long, conflicts_with = "path")]
+    fee: Option<u32>,
+    /// Multi-hop path such as WETH:500:USDC:3000:DAI (V3) or WETH:USDC:DAI (V2)
+    #[clap(long, conflicts_with_all = ["token_in", "token_out"])]
+    path: Option<String>,
+}
+
+impl RouteArgs {
+    /// The identifiers of the first and last token of the route.
+    fn endpoints(&self) -> Option<(&str, &str)> {
+        match (&self.path, &self.token_in, &self.token_out) {
+            (Some(path), _, _) => {
+                let mut tokens = path.split(':').map(str::trim);
+                let first = tokens.next()?;
+                Some((first, tokens.next_back().unwrap_or(first)))
+            }
+            (None, Some(token_in), Some(token_out)) => Some((token_in, token_out)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("amount").required(true).args(["amount_in", "amount_out"])))]
+struct QuoteUniswapArgs {
+    #[clap(long, value_enum, default_value_t = Version::V3)]
+    version: Version,
+    #[clap(flatten)]
+    route: RouteArgs,
+    /// Exact amount to sell
+    #[clap(long)]
+    amount_in: Option<String>,
+    /// Exact amount to buy
+    #[clap(long)]
+    amount_out: Option<String>,
+    /// Slippage tolerance used for the minimum output, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct TwapGuardArgs {
+    /// Refuse the swap if the spot price of a pool on the route deviates
+    /// from its TWAP by more than this many basis points
+    #[clap(long)]
+    max_twap_deviation_bps: Option<u32>,
+    /// TWAP window of --max-twap-deviation-bps, in seconds
+    #[clap(long, default_value_t = uniswap::DEFAULT_TWAP_WINDOW, requires = "max_twap_deviation_bps")]
+    twap_window: u32,
+}
+
+impl TwapGuardArgs {
+    fn guard(&self) -> Option<uniswap::TwapGuard> {
+        self.max_twap_deviation_bps
+            .map(|max_deviation_bps| uniswap::TwapGuard {
+                window: self.twap_window,
+                max_deviation_bps,
+            })
+    }
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("amount").required(true).args(["amount_in", "exact_out"])))]
 struct SwapTokensUniswapV3Args {
+    #[clap(flatten)]
+    route: RouteArgs,
+    /// Exact amount to sell
     #[clap(long)]
-    token_in: String,
+    amount_in: Option<String>,
+    /// Exact amount to buy
     #[clap(long)]
-    token_out: String,
+    exact_out: Option<String>,
+    /// Minimum output, derived from the on-chain quote and --slippage-bps if omitted
+    #[clap(long, conflicts_with = "exact_out")]
+    amount_out_minimum: Option<String>,
+    /// Maximum input for --exact-out, derived from the on-chain quote and
+    /// --slippage-bps if omitted
+    #[clap(long, conflicts_with = "amount_in")]
+    max_in: Option<String>,
+    /// Slippage tolerance used for the minimum output or maximum input, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    #[clap(flatten)]
+    twap_guard: TwapGuardArgs,
     #[clap(long)]
-    amount_in: String,
+    recipient: String,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("amount").required(true).args(["amount_in", "amount_out"])))]
+struct SwapTokensUniswapV2Args {
+    /// Tokens to swap, ETH at either end swaps native ETH through WETH
+    #[clap(flatten)]
+    route: RouteArgs,
+    /// Exact amount to sell
     #[clap(long)]
-    amount_out_minimum: String,
+    amount_in: Option<String>,
+    /// Exact amount to buy
+    #[clap(long)]
+    amount_out: Option<String>,
+    /// Use the router functions supporting tokens that take a fee on
+    /// transfer, implied for tokens whitelisted as fee-on-transfer
+    #[clap(long, conflicts_with = "amount_out")]
+    supporting_fee_on_transfer: bool,
+    /// Slippage tolerance used for the minimum output or maximum input, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    #[clap(flatten)]
+    twap_guard: TwapGuardArgs,
     #[clap(long)]
     recipient: String,
     #[clap(long, default_value = "ethereum")]
@@ -293,29 +585,283 @@ struct SwapTokensUniswapV3Args {
 
 #[derive(Args)]
 struct AddLiquidityUniswapV2Args {
+    /// One of the pair's tokens, ETH to pay native ETH instead of WETH
     #[clap(long)]
     token_a: String,
     #[clap(long)]
     token_b: String,
     #[clap(long)]
     amount_a_desired: String,
+    /// Minimum of token A to deposit, derived from --slippage-bps if omitted
     #[clap(long)]
-    amount_a_min: String,
+    amount_a_min: Option<String>,
+    /// Slippage tolerance applied to the desired amounts, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    /// Price of token A in token B, required to create a pair or to fund an
+    /// empty one
     #[clap(long)]
-    to: String,
+    initial_price: Option<f64>,
     #[clap(long)]
+    to: String,
+    /// Unix timestamp after which the deposit reverts, one hour from now if 0
+    #[clap(long, default_value_t = 0)]
     deadline: u64,
     #[clap(long, default_value = "ethereum")]
     network: String,
 }
 
+#[derive(Args)]
+#[clap(group(ArgGroup::new("share").required(true).args(["percent", "liquidity"])))]
+struct RemoveLiquidityUniswapV2Args {
+    /// One of the pair's tokens, ETH to receive native ETH for WETH
+    #[clap(long)]
+    token_a: String,
+    #[clap(long)]
+    token_b: String,
+    /// Share of the position to withdraw, from 0.01 to 100
+    #[clap(long)]
+    percent: Option<f64>,
+    /// Amount of LP tokens to withdraw
+    #[clap(long)]
+    liquidity: Option<String>,
+    /// Slippage tolerance applied to the expected amounts, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    /// Authorize the router with an EIP-2612 permit signature instead of an
+    /// approval transaction
+    #[clap(long)]
+    permit: bool,
+    #[clap(long)]
+    to: String,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct UniswapV3PositionArgs {
+    #[clap(subcommand)]
+    command: UniswapV3PositionCommand,
+}
+
+#[derive(Subcommand)]
+enum UniswapV3PositionCommand {
+    Mint(MintUniswapV3PositionArgs),
+    Increase(IncreaseUniswapV3LiquidityArgs),
+    Decrease(DecreaseUniswapV3LiquidityArgs),
+    Collect(CollectUniswapV3FeesArgs),
+    Burn(BurnUniswapV3PositionArgs),
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("range").required(true).args(["price_lower", "tick_lower"])))]
+#[clap(group(ArgGroup::new("amount").required(true).multiple(true).args(["amount_a", "amount_b"])))]
+struct MintUniswapV3PositionArgs {
+    #[clap(long)]
+    token_a: String,
+    #[clap(long)]
+    token_b: String,
+    /// Pool fee in hundredths of a bip (100, 500, 3000, 10000)
+    #[clap(long)]
+    fee: u32,
+    /// Lower bound of the range, as a price of token A in token B
+    #[clap(long, requires = "price_upper")]
+    price_lower: Option<f64>,
+    #[clap(long, requires = "price_lower")]
+    price_upper: Option<f64>,
+    /// Lower bound of the range, as a pool tick
+    #[clap(long, requires = "tick_upper", allow_hyphen_values = true)]
+    tick_lower: Option<i32>,
+    #[clap(long, requires = "tick_lower", allow_hyphen_values = true)]
+    tick_upper: Option<i32>,
+    /// Amount of token A to deposit, the amount of token B is derived from
+    /// the range if omitted
+    #[clap(long)]
+    amount_a: Option<String>,
+    #[clap(long)]
+    amount_b: Option<String>,
+    /// Slippage tolerance applied to the expected amounts, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    /// Owner of the position, defaults to the wallet
+    #[clap(long)]
+    recipient: Option<String>,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct IncreaseUniswapV3LiquidityArgs {
+    #[clap(long)]
+    token_id: String,
+    /// One of the position's tokens, the amount of the other one is derived
+    /// from the range
+    #[clap(long)]
+    token: String,
+    #[clap(long)]
+    amount: String,
+    /// Slippage tolerance applied to the expected amounts, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("share").required(true).args(["percent", "liquidity"])))]
+struct DecreaseUniswapV3LiquidityArgs {
+    #[clap(long)]
+    token_id: String,
+    /// Share of the position to withdraw, from 0.01 to 100
+    #[clap(long)]
+    percent: Option<f64>,
+    /// Raw amount of liquidity to withdraw
+    #[clap(long)]
+    liquidity: Option<String>,
+    /// Slippage tolerance applied to the expected amounts, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    /// Receiver of the withdrawn tokens and fees, defaults to the wallet
+    #[clap(long)]
+    recipient: Option<String>,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct CollectUniswapV3FeesArgs {
+    #[clap(long)]
+    token_id: String,
+    /// Receiver of the fees, defaults to the wallet
+    #[clap(long)]
+    recipient: Option<String>,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct BurnUniswapV3PositionArgs {
+    #[clap(long)]
+    token_id: String,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct LpPositionsArgs {
+    /// Wallet address or whitelisted wallet name
+    #[clap(long)]
+    wallet: String,
+    /// First block to scan for deposits, when the explorer does not know where
+    /// the wallet minted its LP tokens or positions
+    #[clap(long, default_value_t = 0)]
+    from_block: u64,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("target").required(true).args(["pool", "token_a"])))]
+struct InspectPoolArgs {
+    /// Address of a V2 pair or V3 pool
+    #[clap(long)]
+    pool: Option<String>,
+    #[clap(long, requires = "token_b")]
+    token_a: Option<String>,
+    #[clap(long, requires = "token_a")]
+    token_b: Option<String>,
+    /// V3 pool fee in hundredths of a bip (100, 500, 3000, 10000), the V2
+    /// pair of the tokens is inspected if omitted
+    #[clap(long, requires = "token_a")]
+    fee: Option<u32>,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("target").required(true).args(["pool", "token_a"])))]
+struct TwapArgs {
+    /// Address of a V3 pool
+    #[clap(long)]
+    pool: Option<String>,
+    #[clap(long, requires = "token_b")]
+    token_a: Option<String>,
+    #[clap(long, requires = "token_a")]
+    token_b: Option<String>,
+    /// Pool fee in hundredths of a bip, defaults to the tier with the most
+    /// liquidity
+    #[clap(long, requires = "token_a")]
+    fee: Option<u32>,
+    /// Averaging window in seconds
+    #[clap(long, default_value_t = uniswap::DEFAULT_TWAP_WINDOW)]
+    window: u32,
+    /// Send a transaction raising the pool's observation cardinality when its
+    /// observations do not cover the window
+    #[clap(long)]
+    increase_cardinality: bool,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct BestSwapArgs {
+    #[clap(long)]
+    token_in: String,
+    #[clap(long)]
+    token_out: String,
+    /// Exact amount to sell
+    #[clap(long)]
+    amount_in: String,
+    /// Slippage tolerance used for the minimum output, in basis points
+    #[clap(long, default_value_t = uniswap::DEFAULT_SLIPPAGE_BPS, value_parser = clap::value_parser!(u32).range(0..=10_000))]
+    slippage_bps: u32,
+    #[clap(long)]
+    recipient: String,
+    /// Only compare the quotes, without swapping
+    #[clap(long)]
+    quote_only: bool,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct AllowancesArgs {
+    /// Wallet address or whitelisted wallet name, every wallet whitelisted on
+    /// the network by default
+    #[clap(long)]
+    wallet: Option<String>,
+    /// First block to scan for Approval logs
+    #[clap(long, default_value_t = 0)]
+    from_block: u64,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
+#[derive(Args)]
+struct RevokeArgs {
+    /// Token of each allowance to revoke, paired in order with --spender
+    #[clap(long, required = true)]
+    token: Vec<String>,
+    /// Spender of each allowance to revoke, paired in order with --token
+    #[clap(long, required = true)]
+    spender: Vec<String>,
+    /// Revoke without asking for confirmation
+    #[clap(long)]
+    yes: bool,
+    #[clap(long, default_value = "ethereum")]
+    network: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
     let cli = Cli::parse();
 
     let whitelist = Arc::new(load_or_create_whitelist()?);
-    let resolver = Resolver::new(Arc::clone(&whitelist));
+    if let Some(reason) = whitelist.read_only_reason() {
+        eprintln!("Warning: whitelist is read-only: {}", reason);
+    }
+    let resolver = Resolver::new(Arc::clone(&whitelist)).with_token_list(cli.use_token_list);
 
     match cli.command {
         Command::GetBlockNumber(args) => {
@@ -337,14 +883,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::GetBalance(args) => {
             let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let address = resolver.resolve(&args.address, chain)?;
+            let address = resolver.resolve(&args.address, chain).await?;
             evm_interface.get_balance(address.to_string()).await?;
         }
         Command::GetNonce(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
             let whitelist = Arc::clone(&whitelist);
-            let address = resolver.resolve(&args.address, chain)?;
+            let address = resolver.resolve(&args.address, chain).await?;
             evm_interface.get_nonce(address.to_string()).await?;
         }
         Command::GetBlockDetails(args) => {
@@ -358,12 +904,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Command::GetTxDetails(args) => {
             let evm_interface = EVMInterface::new(&args.network, whitelist).await?;
-            evm_interface.get_tx_details(args.tx_hash).await?;
+            evm_interface
+                .get_tx_details(args.tx_hash, &resolver)
+                .await?;
         }
         Command::GenerateContractBindings(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let contract_address = resolver.resolve(&args.contract_address, chain)?;
+            let contract_address = resolver.resolve(&args.contract_address, chain).await?;
             println!(
                 "Generating contract bindings for {} on {}",
                 contract_address, args.network
@@ -375,7 +923,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::GenerateSourceCode(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let contract_address = resolver.resolve(&args.contract_address, chain)?;
+            let contract_address = resolver.resolve(&args.contract_address, chain).await?;
             evm_interface
                 .generate_source_code(contract_address.to_string(), args.contract_name)
                 .await?;
@@ -383,8 +931,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::GetERC20Balance(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let wallet_address = resolver.resolve(&args.wallet_address, chain)?;
-            let token_address = resolver.resolve(&args.token_address, chain)?;
+            let wallet_address = resolver.resolve(&args.wallet_address, chain).await?;
+            let token_address = resolver.resolve(&args.token_address, chain).await?;
             evm_interface
                 .get_erc_20_balances(wallet_address.to_string(), token_address.to_string())
                 .await?;
@@ -396,22 +944,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::SendETH(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let to_address = resolver.resolve(&args.to_address, chain)?;
-            evm_interface
-                .send_eth(to_address.to_string(), args.amount)
-                .await?;
+            let to_address = resolver.resolve(&args.to_address, chain).await?;
+            evm_interface.send_eth(to_address, args.amount).await?;
         }
         Command::SendERC20(args) => {
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
             let chain = get_chain_from_string(&args.network).unwrap();
-            let token_address = resolver.resolve(&args.token_address, chain)?;
-            let to_address = resolver.resolve(&args.to_address, chain)?;
+            let token_address = resolver.resolve(&args.token_address, chain).await?;
+            let to_address = resolver.resolve(&args.to_address, chain).await?;
             evm_interface
-                .send_erc20(
-                    token_address.to_string(),
-                    to_address.to_string(),
-                    args.amount,
-                )
+                .send_erc20(token_address, to_address, args.amount)
                 .await?;
         }
         Command::GetSupportedChains => {
@@ -421,21 +963,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Command::GetKnownTokens(args) => {
             let bridge = bridge::lifi::LiFiBridge::new();
-            let tokens = bridge.get_known_tokens(&args.chain).await?;
+            let chain = get_chain_from_string(&args.chain)
+                .ok_or_else(|| format!("Unsupported network: {}", args.chain))?;
+            let tokens = bridge.get_known_tokens(&(chain as u64).to_string()).await?;
             print_lifi_tokens(&tokens);
             // println!("Known tokens on {}: {:?}", args.chain, tokens);
         }
         Command::RequestRoutes(args) => {
             let bridge = bridge::lifi::LiFiBridge::new();
-            let from_chain = get_chain_from_string(&args.from_chain_id.to_string()).unwrap();
-            let to_chain = get_chain_from_string(&args.to_chain_id.to_string()).unwrap();
-            let from_token_address = resolver.resolve(&args.from_token_address, from_chain)?;
-            let to_token_address = resolver.resolve(&args.to_token_address, to_chain)?;
+            let (from_token_address, from_chain) = resolver
+                .resolve_with_chain(&args.from_token_address, parse_chain(&args.from_chain)?)
+                .await?;
+            let (to_token_address, to_chain) = resolver
+                .resolve_with_chain(&args.to_token_address, parse_chain(&args.to_chain)?)
+                .await?;
             let request = bridge::lifi_types::RouteRequest::new(
-                args.from_chain_id,
-                args.to_chain_id,
-                from_token_address.to_string(),
-                to_token_address.to_string(),
+                from_chain as u64,
+                to_chain as u64,
+                format!("{:?}", from_token_address),
+                format!("{:?}", to_token_address),
                 args.from_amount,
                 // args.from_address,
                 // args.to_address,
@@ -445,20 +991,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Command::RequestQuote(args) => {
             let bridge = bridge::lifi::LiFiBridge::new();
-            let from_chain = get_chain_from_string(&args.from_chain).unwrap();
-            let to_chain = get_chain_from_string(&args.to_chain).unwrap();
-            let from_token = resolver.resolve(&args.from_token, from_chain)?;
-            let to_token = resolver.resolve(&args.to_token, to_chain)?;
-            let from_address = resolver.resolve(&args.from_address, from_chain)?;
-            let to_address = resolver.resolve(&args.to_address, to_chain)?;
+            let (from_token, from_chain) = resolver
+                .resolve_with_chain(&args.from_token, parse_chain(&args.from_chain)?)
+                .await?;
+            let (to_token, to_chain) = resolver
+                .resolve_with_chain(&args.to_token, parse_chain(&args.to_chain)?)
+                .await?;
+            let from_address = resolver.resolve(&args.from_address, from_chain).await?;
+            let to_address = resolver.resolve(&args.to_address, to_chain).await?;
             let request = bridge::lifi_types::QuoteRequest::new(
-                args.from_chain,
-                args.to_chain,
-                from_token.to_string(),
-                to_token.to_string(),
+                (from_chain as u64).to_string(),
+                (to_chain as u64).to_string(),
+                format!("{:?}", from_token),
+                format!("{:?}", to_token),
                 args.from_amount,
-                from_address.to_string(),
-                to_address.to_string(),
+                format!("{:?}", from_address),
+                format!("{:?}", to_address),
             );
             let quote = bridge.request_quote(request).await?;
             println!("Quote: {:?}", quote);
@@ -476,27 +1024,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Command::GetConnections(args) => {
             let bridge = bridge::lifi::LiFiBridge::new();
-            let from_chain = Chain::Mainnet;
-            let to_chain = Chain::Mainnet;
-            // let from_chain = get_chain_from_string(&args.from_chain).unwrap();
-            // let to_chain = get_chain_from_string(&args.to_chain).unwrap();
-            // let from_chain = args.from_chain.as_ref().map(|c| get_chain_from_string(c));
-            // let to_chain = args.to_chain.as_ref().map(|c| get_chain_from_string(c));
-            let from_token = args
-                .from_token
-                .as_ref()
-                .map(|t| resolver.resolve(t, from_chain))
-                .transpose()?;
-            let to_token = args
-                .to_token
-                .as_ref()
-                .map(|t| resolver.resolve(t, to_chain))
-                .transpose()?;
+            let mut from_chain = parse_chain(&args.from_chain)?;
+            let mut to_chain = parse_chain(&args.to_chain)?;
+            let from_token = match &args.from_token {
+                Some(token) => {
+                    let (address, chain) = resolver.resolve_with_chain(token, from_chain).await?;
+                    from_chain = Some(chain);
+                    Some(address)
+                }
+                None => None,
+            };
+            let to_token = match &args.to_token {
+                Some(token) => {
+                    let (address, chain) = resolver.resolve_with_chain(token, to_chain).await?;
+                    to_chain = Some(chain);
+                    Some(address)
+                }
+                None => None,
+            };
             let request = bridge::lifi_types::ConnectionsRequest::new(
-                args.from_chain,
-                args.to_chain,
-                from_token.map(|t| t.to_string()),
-                to_token.map(|t| t.to_string()),
+                from_chain.map(|chain| (chain as u64).to_string()),
+                to_chain.map(|chain| (chain as u64).to_string()),
+                from_token.map(|t| format!("{:?}", t)),
+                to_token.map(|t| format!("{:?}", t)),
                 args.from_amount,
                 args.allow_exchanges,
             );
@@ -506,21 +1056,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::GetTransactions(args) => {
             let chain = get_chain_from_string(&args.network).unwrap();
             let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
-            let address = resolver.resolve(&args.address, chain)?;
-            evm_interface.get_transactions(address.to_string()).await?;
+            let address = resolver.resolve(&args.address, chain).await?;
+            evm_interface.get_transactions(address, &resolver).await?;
+        }
+        Command::ScanAddressPoisoning(args) => {
+            let chain = get_chain_from_string(&args.network)
+                .ok_or_else(|| format!("Unsupported network: {}", args.network))?;
+            let evm_interface = EVMInterface::new(&args.network, Arc::clone(&whitelist)).await?;
+            let wallet = resolver.resolve(&args.wallet, chain).await?;
+            evm_interface
+                .scan_address_poisoning(wallet, &resolver)
+                .await?;
         }
         Command::AddWalletToWhitelist(args) => {
             let mut whitelist = load_or_create_whitelist()?;
+            let address = Address::from_str(&args.address)?;
+            let chain_id = get_chain_scope_from_string(&args.chain)?;
+            let lookalikes = lookalike::find_lookalikes(&whitelist, chain_id, address);
+            if !lookalikes.is_empty() {
+                println!(
+                    "Warning: {:?} looks like wallets already in the whitelist:\n  {}",
+                    address,
+                    lookalike::describe(&lookalikes)
+                );
+                if !utils::confirm("Add it anyway?")? {
+                    return Ok(());
+                }
+            }
             whitelist.add_wallet_address(
-                args.address.clone(),
+                address,
                 Some(args.name.clone().unwrap_or(args.address)),
+                chain_id,
             );
             whitelist.save(&get_whitelist_path().to_string_lossy())?;
             println!("Wallet address added to whitelist.");
         }
         Command::RemoveWalletFromWhitelist(args) => {
             let mut whitelist = load_or_create_whitelist()?;
-            whitelist.remove_wallet_address(&args.address);
+            let address = Address::from_str(&args.address)?;
+            let chain_id = get_chain_scope_from_string(&args.chain)?;
+            whitelist.remove_wallet_address(address, chain_id);
             whitelist.save(&get_whitelist_path().to_string_lossy())?;
             println!("Wallet address removed from whitelist.");
         }
@@ -528,8 +1103,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut whitelist = load_or_create_whitelist()?;
             let chain = get_chain_from_string(&args.chain).unwrap();
             let config = get_chain_config(chain).await;
+            let address = Address::from_str(&args.address)?;
             whitelist
-                .add_token_address(args.address, config.chain_id, args.name, config.http)
+                .add_token_address(
+                    address,
+                    config.chain_id,
+                    args.name,
+                    args.token_type,
+                    config.http,
+                )
                 .await?;
             whitelist.save(&get_whitelist_path().to_string_lossy())?;
             println!("Token address added to whitelist.");
@@ -537,58 +1119,592 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::RemoveTokenFromWhitelist(args) => {
             let mut whitelist = load_or_create_whitelist()?;
             let chain_id = get_chain_id_from_string(&args.chain).unwrap();
-            whitelist.remove_token_address(&args.address, chain_id);
+            let address = Address::from_str(&args.address)?;
+            whitelist.remove_token_address(address, chain_id);
             whitelist.save(&get_whitelist_path().to_string_lossy())?;
             println!("Token address removed from whitelist.");
         }
         Command::ShowWhitelist => {
             let whitelist = load_or_create_whitelist()?;
-            println!("Whitelisted wallet addresses:");
-            for (address, info) in whitelist.get_wallet_addresses() {
+            let now = utils::unix_now();
+            let status = |active_from: u64| {
+                if active_from > now {
+                    format!(
+                        " [pending, active in {}]",
+                        utils::format_duration(active_from - now)
+                    )
+                } else {
+                    String::new()
+                }
+            };
+            println!(
+                "Activation delay: {}",
+                utils::format_duration(whitelist.activation_delay())
+            );
+            if let Some(change) = whitelist.pending_activation_delay() {
                 println!(
-                    "{} ({})",
-                    address,
-                    info.name.as_ref().unwrap_or(&String::new())
+                    "Activation delay changes to {} in {}",
+                    utils::format_duration(change.delay_secs),
+                    utils::format_duration(change.effective_from - now)
+                );
+            }
+            println!("\nWhitelisted wallet addresses:");
+            for info in whitelist.get_wallet_addresses().values() {
+                let chain = info
+                    .chain_id
+                    .map_or("all chains".to_string(), |id| id.to_string());
+                println!(
+                    "{} ({}) on {}{}",
+                    info.address,
+                    info.name.as_ref().unwrap_or(&String::new()),
+                    chain,
+                    status(info.active_from)
                 );
             }
             println!("\nWhitelisted token addresses:");
-            for (address, info) in whitelist.get_token_addresses() {
-                println!("{}: {} on {}", info.symbol, address, info.chain_id);
+            for info in whitelist.get_token_addresses().values() {
+                let decimals = info.decimals.map_or("?".to_string(), |d| d.to_string());
+                println!(
+                    "{}: {} on {} ({} decimals, {}){}",
+                    info.symbol,
+                    info.address,
+                    info.chain_id,
+                    decimals,
+                    info.token_type,
+                    status(info.active_from)
+                );
+            }
+            println!("\nSpending policies:");
+            for policy in whitelist.get_spending_policies() {
+                let limit = |value: Option<U256>| value.map_or("-".to_string(), |v| v.to_string());
+                println!(
+                    "{}: max per tx {}, daily cap {}, approval above {}",
+                    policy.describe(),
+                    limit(policy.max_per_tx),
+                    limit(policy.daily_cap),
+                    limit(policy.approval_threshold)
+                );
             }
         }
-        Command::SwapTokensUniswapV3(args) => {
+        Command::SignWhitelist => {
+            let mut whitelist = load_or_create_whitelist()?;
+            if let Some(reason) = whitelist.read_only_reason() {
+                println!("Whitelist failed verification: {}", reason);
+                println!("Review the content with show-whitelist before signing it.");
+                if !utils::confirm("Sign the current whitelist as reviewed?")? {
+                    return Ok(());
+                }
+            }
+            whitelist.clear_read_only();
+            whitelist.save(&get_whitelist_path().to_string_lossy())?;
+            println!("Whitelist signed.");
+        }
+        Command::SetWhitelistDelay(args) => {
+            let mut whitelist = load_or_create_whitelist()?;
+            whitelist.set_activation_delay(args.hours * 3600);
+            whitelist.save(&get_whitelist_path().to_string_lossy())?;
+            match whitelist.pending_activation_delay() {
+                Some(change) => println!(
+                    "Activation delay will be lowered to {} once the current delay has elapsed.",
+                    utils::format_duration(change.delay_secs)
+                ),
+                None => println!("Activation delay set to {} hours.", args.hours),
+            }
+        }
+        Command::Whitelist(args) => match args.command {
+            WhitelistCommand::Export(args) => {
+                let whitelist = load_or_create_whitelist()?;
+                let format = args.format.unwrap_or_else(|| {
+                    args.output
+                        .as_deref()
+                        .map_or(ExportFormat::Json, ExportFormat::from_path)
+                });
+                let content = WhitelistExport::from_whitelist(&whitelist).render(format)?;
+                match args.output {
+                    Some(path) => {
+                        fs::write(&path, content)?;
+                        println!("Whitelist exported to {}", path);
+                    }
+                    None => print!("{}", content),
+                }
+            }
+            WhitelistCommand::Import(args) => {
+                let mut whitelist = load_or_create_whitelist()?;
+                let format = args
+                    .format
+                    .unwrap_or_else(|| ExportFormat::from_path(&args.input));
+                let import = WhitelistExport::parse(&fs::read_to_string(&args.input)?, format)?;
+                let onchain_metadata = if args.skip_onchain_check {
+                    Default::default()
+                } else {
+                    whitelist_io::fetch_import_metadata(&import.tokens).await
+                };
+                let plan = whitelist_io::plan_import(&whitelist, &import, &onchain_metadata);
+                plan.print();
+                if plan.changes.is_empty() {
+                    return Ok(());
+                }
+                if !args.yes && !utils::confirm("Write these changes to the whitelist?")? {
+                    return Ok(());
+                }
+                plan.apply(&mut whitelist);
+                whitelist.save(&get_whitelist_path().to_string_lossy())?;
+                println!("Whitelist updated.");
+            }
+            WhitelistCommand::Refresh(args) => {
+                let mut whitelist = load_or_create_whitelist()?;
+                let chain_id = args
+                    .chain
+                    .as_deref()
+                    .map(|chain| {
+                        get_chain_id_from_string(chain)
+                            .ok_or_else(|| format!("Unsupported network: {}", chain))
+                    })
+                    .transpose()?;
+                let drifts = whitelist_io::check_token_drift(&whitelist, chain_id).await;
+                if drifts.is_empty() {
+                    println!("All whitelisted tokens match their recorded metadata.");
+                    return Ok(());
+                }
+                for drift in &drifts {
+                    println!(
+                        "! {} {:?} on chain {}:",
+                        drift.symbol, drift.address, drift.chain_id
+                    );
+                    for difference in &drift.differences {
+                        println!("    {}", difference);
+                    }
+                }
+                if !args.accept {
+                    println!("\nReview the changes and re-run with --accept to record them.");
+                    return Ok(());
+                }
+                for drift in drifts {
+                    if let Some(current) = drift.current {
+                        whitelist.update_token_metadata(drift.address, drift.chain_id, current);
+                    }
+                }
+                whitelist.save(&get_whitelist_path().to_string_lossy())?;
+                println!("Token metadata updated.");
+            }
+        },
+        Command::Addressbook(args) => match args.command {
+            AddressbookCommand::Verify(args) => {
+                addressbook::verify(parse_chain(&args.chain)?).await?;
+                println!("All addressbook entries have deployed code.");
+            }
+            AddressbookCommand::Sign => {
+                addressbook::sign()?;
+                println!("Signed {}.", get_addressbook_path().display());
+            }
+            AddressbookCommand::Discover(args) => {
+                let chain = get_chain_from_string(&args.chain)
+                    .ok_or_else(|| format!("Unsupported network: {}", args.chain))?;
+                let routers = args
+                    .router
+                    .iter()
+                    .map(|router| Address::from_str(router))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let weth = args.weth.as_deref().map(Address::from_str).transpose()?;
+                let proposed = addressbook::discover(chain, &routers, weth).await?;
+                for (name, chains) in &proposed {
+                    for address in chains.values() {
+                        match addressbook::contract_address(name.as_str(), chain) {
+                            None => println!("+ {}: {:?}", name, address),
+                            Some(current) if current == *address => {
+                                println!("= {}: {:?}", name, address)
+                            }
+                            Some(current) => println!(
+                                "! {}: {:?}, the addressbook has {:?}",
+                                name, address, current
+                            ),
+                        }
+                    }
+                }
+                let output = args
+                    .output
+                    .unwrap_or_else(|| format!("addressbook.{}.proposed.json", chain));
+                fs::write(&output, serde_json::to_string_pretty(&proposed)? + "\n")?;
+                println!(
+                    "Proposed entries written to {}. Review them before merging into {}.",
+                    output,
+                    get_addressbook_path().display()
+                );
+            }
+        },
+        Command::SetSpendingPolicy(args) => {
+            let mut whitelist = load_or_create_whitelist()?;
+            let chain = get_chain_from_string(&args.chain)
+                .ok_or_else(|| format!("Unsupported network: {}", args.chain))?;
+            let token = resolve_policy_token(&resolver, &args.token, chain).await?;
+            let wallet = match &args.wallet {
+                Some(wallet) => Some(resolver.resolve(wallet, chain).await?),
+                None => None,
+            };
+            let parse_amount =
+                |value: &Option<String>| value.as_deref().map(U256::from_dec_str).transpose();
+            whitelist.set_spending_policy(SpendingPolicy {
+                chain_id: chain as u64,
+                token: token.map(|t| format!("{:?}", t)),
+                wallet: wallet.map(|w| format!("{:?}", w)),
+                max_per_tx: parse_amount(&args.max_per_tx)?,
+                daily_cap: parse_amount(&args.daily_cap)?,
+                approval_threshold: parse_amount(&args.approval_threshold)?,
+            });
+            whitelist.save(&get_whitelist_path().to_string_lossy())?;
+            println!("Spending policy saved.");
+        }
+        Command::RemoveSpendingPolicy(args) => {
+            let mut whitelist = load_or_create_whitelist()?;
+            let chain = get_chain_from_string(&args.chain)
+                .ok_or_else(|| format!("Unsupported network: {}", args.chain))?;
+            let token = resolve_policy_token(&resolver, &args.token, chain).await?;
+            let wallet = match &args.wallet {
+                Some(wallet) => Some(resolver.resolve(wallet, chain).await?),
+                None => None,
+            };
+            whitelist.remove_spending_policy(chain as u64, token, wallet);
+            whitelist.save(&get_whitelist_path().to_string_lossy())?;
+            println!("Spending policy removed.");
+        }
+        Command::QuoteUniswap(args) => {
             let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            let route =
+                resolve_route(&args.route, args.version, &resolver, &evm_interface, chain).await?;
+            let (kind, amount) = swap_amount(args.amount_in, args.amount_out)?;
+            evm_interface
+                .quote_uniswap(route, kind, amount, args.slippage_bps)
+                .await?;
+        }
+        Command::SwapTokensUniswapV3(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                .await?
+                .with_approval_mode(cli.approval);
             let chain = get_chain_from_string(&args.network).unwrap();
-            let token_in = resolver.resolve(&args.token_in, chain)?;
-            let token_out = resolver.resolve(&args.token_out, chain)?;
-            let recipient = resolver.resolve(&args.recipient, chain)?;
+            if let Some((first, last)) = args.route.endpoints() {
+                if is_native(first) || is_native(last) {
+                    return Err("Uniswap V3 swaps do not take native ETH, use WETH".into());
+                }
+            }
+            let Route::V3(path) =
+                resolve_route(&args.route, Version::V3, &resolver, &evm_interface, chain).await?
+            else {
+                unreachable!("V3 routes are always swap paths");
+            };
+            let (kind, amount) = swap_amount(args.amount_in, args.exact_out)?;
+            let recipient = resolver.resolve(&args.recipient, chain).await?;
             evm_interface
                 .swap_tokens_uniswap_v3(
-                    token_in.to_string(),
-                    token_out.to_string(),
-                    args.amount_in,
-                    args.amount_out_minimum,
-                    recipient.to_string(),
+                    path,
+                    kind,
+                    amount,
+                    args.amount_out_minimum.or(args.max_in),
+                    args.slippage_bps,
+                    args.twap_guard.guard(),
+                    recipient,
+                )
+                .await?;
+        }
+        Command::SwapTokensUniswapV2(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                .await?
+                .with_approval_mode(cli.approval);
+            let chain = evm_interface.chain();
+            let (native_in, native_out) = args
+                .route
+                .endpoints()
+                .map(|(first, last)| (is_native(first), is_native(last)))
+                .unwrap_or_default();
+            if native_in && native_out {
+                return Err("Cannot swap ETH for ETH".into());
+            }
+            let Route::V2(path) =
+                resolve_route(&args.route, Version::V2, &resolver, &evm_interface, chain).await?
+            else {
+                unreachable!("V2 routes are always token lists");
+            };
+            let (kind, amount) = swap_amount(args.amount_in, args.amount_out)?;
+            let recipient = resolver.resolve(&args.recipient, chain).await?;
+            evm_interface
+                .swap_tokens_uniswap_v2(
+                    path,
+                    kind,
+                    amount,
+                    native_in,
+                    native_out,
+                    args.supporting_fee_on_transfer,
+                    args.slippage_bps,
+                    args.twap_guard.guard(),
+                    recipient,
                 )
                 .await?;
         }
         Command::AddLiquidityUniswapV2(args) => {
-            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                .await?
+                .with_approval_mode(cli.approval);
             let chain = get_chain_from_string(&args.network).unwrap();
-            let token_a = resolver.resolve(&args.token_a, chain)?;
-            let token_b = resolver.resolve(&args.token_b, chain)?;
-            let to = resolver.resolve(&args.to, chain)?;
+            if is_native(&args.token_a) && is_native(&args.token_b) {
+                return Err("Cannot add ETH/ETH liquidity".into());
+            }
+            let native_eth = is_native(&args.token_a) || is_native(&args.token_b);
+            let token_a = resolve_route_token(&resolver, &args.token_a, chain).await?;
+            let token_b = resolve_route_token(&resolver, &args.token_b, chain).await?;
+            let to = resolver.resolve(&args.to, chain).await?;
             evm_interface
                 .add_liquidity_uniswap_v2(
-                    token_a.to_string(),
-                    token_b.to_string(),
+                    token_a,
+                    token_b,
                     args.amount_a_desired,
                     args.amount_a_min,
-                    to.to_string(),
+                    args.initial_price,
+                    native_eth,
+                    args.slippage_bps,
+                    to,
                     args.deadline,
                 )
                 .await?;
         }
+        Command::RemoveLiquidityUniswapV2(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                .await?
+                .with_approval_mode(cli.approval);
+            let chain = evm_interface.chain();
+            // The router's ETH functions take the token first and WETH second
+            let (token_a, token_b) = if is_native(&args.token_a) {
+                (&args.token_b, &args.token_a)
+            } else {
+                (&args.token_a, &args.token_b)
+            };
+            if is_native(token_a) {
+                return Err("Cannot remove ETH/ETH liquidity".into());
+            }
+            let native_b = is_native(token_b);
+            let token_a = resolve_route_token(&resolver, token_a, chain).await?;
+            let token_b = resolve_route_token(&resolver, token_b, chain).await?;
+            let liquidity = match &args.liquidity {
+                Some(liquidity) => Some(ethers::utils::parse_units(liquidity, 18)?.into()),
+                None => None,
+            };
+            let share = liquidity_share(args.percent, liquidity)?;
+            let to = resolver.resolve(&args.to, chain).await?;
+            evm_interface
+                .remove_liquidity_uniswap_v2(
+                    token_a,
+                    token_b,
+                    share,
+                    native_b,
+                    args.permit,
+                    args.slippage_bps,
+                    to,
+                )
+                .await?;
+        }
+        Command::UniswapV3Position(args) => match args.command {
+            UniswapV3PositionCommand::Mint(args) => {
+                let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                    .await?
+                    .with_approval_mode(cli.approval);
+                let chain = evm_interface.chain();
+                let token_a = resolver.resolve(&args.token_a, chain).await?;
+                let token_b = resolver.resolve(&args.token_b, chain).await?;
+                if token_a == token_b {
+                    return Err("Token A and token B must differ".into());
+                }
+                let range = match (
+                    args.price_lower,
+                    args.price_upper,
+                    args.tick_lower,
+                    args.tick_upper,
+                ) {
+                    (Some(lower), Some(upper), _, _) => uniswap::PriceRange::Prices(lower, upper),
+                    (_, _, Some(lower), Some(upper)) => uniswap::PriceRange::Ticks(lower, upper),
+                    _ => return Err("A price or tick range is required".into()),
+                };
+                let recipient = match &args.recipient {
+                    Some(recipient) => resolver.resolve(recipient, chain).await?,
+                    None => evm_interface.wallet_address(),
+                };
+                evm_interface
+                    .mint_uniswap_v3_position(
+                        token_a,
+                        token_b,
+                        args.fee,
+                        range,
+                        args.amount_a,
+                        args.amount_b,
+                        args.slippage_bps,
+                        recipient,
+                    )
+                    .await?;
+            }
+            UniswapV3PositionCommand::Increase(args) => {
+                let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                    .await?
+                    .with_approval_mode(cli.approval);
+                let chain = evm_interface.chain();
+                let token = resolver.resolve(&args.token, chain).await?;
+                evm_interface
+                    .increase_uniswap_v3_liquidity(
+                        parse_token_id(&args.token_id)?,
+                        token,
+                        args.amount,
+                        args.slippage_bps,
+                    )
+                    .await?;
+            }
+            UniswapV3PositionCommand::Decrease(args) => {
+                let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+                let chain = evm_interface.chain();
+                let liquidity = match &args.liquidity {
+                    Some(liquidity) => Some(
+                        U256::from_dec_str(liquidity)
+                            .map_err(|_| format!("Invalid liquidity {}", liquidity))?,
+                    ),
+                    None => None,
+                };
+                let share = liquidity_share(args.percent, liquidity)?;
+                let recipient = match &args.recipient {
+                    Some(recipient) => resolver.resolve(recipient, chain).await?,
+                    None => evm_interface.wallet_address(),
+                };
+                evm_interface
+                    .decrease_uniswap_v3_liquidity(
+                        parse_token_id(&args.token_id)?,
+                        share,
+                        args.slippage_bps,
+                        recipient,
+                    )
+                    .await?;
+            }
+            UniswapV3PositionCommand::Collect(args) => {
+                let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+                let chain = evm_interface.chain();
+                let recipient = match &args.recipient {
+                    Some(recipient) => resolver.resolve(recipient, chain).await?,
+                    None => evm_interface.wallet_address(),
+                };
+                evm_interface
+                    .collect_uniswap_v3_fees(parse_token_id(&args.token_id)?, recipient)
+                    .await?;
+            }
+            UniswapV3PositionCommand::Burn(args) => {
+                let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+                evm_interface
+                    .burn_uniswap_v3_position(parse_token_id(&args.token_id)?)
+                    .await?;
+            }
+        },
+        Command::LpPositions(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            let wallet = resolver.resolve(&args.wallet, chain).await?;
+            evm_interface.lp_positions(wallet, args.from_block).await?;
+        }
+        Command::InspectPool(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            let pool = match (&args.pool, &args.token_a, &args.token_b) {
+                (Some(pool), _, _) => resolver.resolve(pool, chain).await?,
+                (None, Some(token_a), Some(token_b)) => {
+                    let token_a = resolve_route_token(&resolver, token_a, chain).await?;
+                    let token_b = resolve_route_token(&resolver, token_b, chain).await?;
+                    evm_interface.find_pool(token_a, token_b, args.fee).await?
+                }
+                _ => return Err("Either --pool or --token-a and --token-b is required".into()),
+            };
+            evm_interface.inspect_pool(pool).await?;
+        }
+        Command::Twap(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            let pool = match (&args.pool, &args.token_a, &args.token_b) {
+                (Some(pool), _, _) => resolver.resolve(pool, chain).await?,
+                (None, Some(token_a), Some(token_b)) => {
+                    let token_a = resolve_route_token(&resolver, token_a, chain).await?;
+                    let token_b = resolve_route_token(&resolver, token_b, chain).await?;
+                    let path = evm_interface
+                        .resolve_v3_path(token_a, token_b, args.fee)
+                        .await?;
+                    evm_interface
+                        .find_pool(token_a, token_b, Some(path.fees[0]))
+                        .await?
+                }
+                _ => return Err("Either --pool or --token-a and --token-b is required".into()),
+            };
+            evm_interface
+                .twap(pool, args.window, args.increase_cardinality)
+                .await?;
+        }
+        Command::BestSwap(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone())
+                .await?
+                .with_approval_mode(cli.approval);
+            let chain = evm_interface.chain();
+            if is_native(&args.token_in) || is_native(&args.token_out) {
+                return Err("best-swap does not swap native ETH, wrap it or use WETH".into());
+            }
+            let token_in = resolver.resolve(&args.token_in, chain).await?;
+            let token_out = resolver.resolve(&args.token_out, chain).await?;
+            let recipient = resolver.resolve(&args.recipient, chain).await?;
+            evm_interface
+                .best_swap(
+                    token_in,
+                    token_out,
+                    args.amount_in,
+                    args.slippage_bps,
+                    recipient,
+                    args.quote_only,
+                )
+                .await?;
+        }
+        Command::Allowances(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            let wallets = match &args.wallet {
+                Some(wallet) => vec![(resolver.resolve(wallet, chain).await?, wallet.clone())],
+                None => {
+                    let mut wallets: Vec<(Address, String)> = whitelist
+                        .get_wallet_addresses()
+                        .values()
+                        .filter(|info| info.applies_to(chain as u64))
+                        .filter_map(|info| {
+                            let address = Address::from_str(&info.address).ok()?;
+                            let name = info.name.clone().unwrap_or_else(|| "unnamed".to_string());
+                            Some((address, name))
+                        })
+                        .collect();
+                    wallets.sort();
+                    wallets.dedup_by_key(|(address, _)| *address);
+                    if wallets.is_empty() {
+                        return Err(format!("No wallet whitelisted on {}", args.network).into());
+                    }
+                    wallets
+                }
+            };
+            evm_interface.allowances(&wallets, args.from_block).await?;
+        }
+        Command::Revoke(args) => {
+            let evm_interface = EVMInterface::new(&args.network, whitelist.clone()).await?;
+            let chain = evm_interface.chain();
+            if args.token.len() != args.spender.len() {
+                return Err(format!(
+                    "{} tokens and {} spenders given, pass one --spender per --token",
+                    args.token.len(),
+                    args.spender.len()
+                )
+                .into());
+            }
+            let mut approvals = Vec::new();
+            for (token, spender) in args.token.iter().zip(&args.spender) {
+                approvals.push((
+                    resolver.resolve(token, chain).await?,
+                    resolver.resolve(spender, chain).await?,
+                ));
+            }
+            evm_interface.revoke(&approvals, args.yes).await?;
+        }
         _ => {
             println!("Unsupported command");
         }
@@ -597,6 +1713,122 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+async fn resolve_policy_token(
+    resolver: &Resolver,
+    token: &str,
+    chain: Chain,
+) -> Result<Option<Address>, Box<dyn std::error::Error>> {
+    if is_native(token) {
+        return Ok(None);
+    }
+    Ok(Some(resolver.resolve(token, chain).await?))
+}
+
+/// Builds the swap route described by `--path` or `--token-in`/`--token-out`.
+async fn resolve_route(
+    args: &RouteArgs,
+    version: Version,
+    resolver: &Resolver,
+    evm_interface: &EVMInterface,
+    chain: Chain,
+) -> Result<Route, Box<dyn std::error::Error>> {
+    if version == Version::V2 && args.fee.is_some() {
+        return Err("--fee only applies to Uniswap V3".into());
+    }
+    match (&args.path, &args.token_in, &args.token_out) {
+        (Some(spec), _, _) => {
+            let (tokens, fees) = match version {
+                Version::V2 => (uniswap::parse_v2_path_spec(spec)?, Vec::new()),
+                Version::V3 => uniswap::parse_path_spec(spec)?,
+            };
+            let mut addresses = Vec::new();
+            for token in &tokens {
+                addresses.push(resolve_route_token(resolver, token, chain).await?);
+            }
+            Ok(match version {
+                Version::V2 => Route::V2(addresses),
+                Version::V3 => Route::V3(SwapPath::new(addresses, fees)?),
+            })
+        }
+        (None, Some(token_in), Some(token_out)) => {
+            let token_in = resolve_route_token(resolver, token_in, chain).await?;
+            let token_out = resolve_route_token(resolver, token_out, chain).await?;
+            Ok(match version {
+                Version::V2 => Route::V2(vec![token_in, token_out]),
+                Version::V3 => Route::V3(
+                    evm_interface
+                        .resolve_v3_path(token_in, token_out, args.fee)
+                        .await?,
+                ),
+            })
+        }
+        _ => Err("Either --path or --token-in and --token-out is required".into()),
+    }
+}
+
+/// Tells exact input from exact output swaps by which amount was given.
+fn swap_amount(
+    amount_in: Option<String>,
+    amount_out: Option<String>,
+) -> Result<(SwapKind, String), String> {
+    match (amount_in, amount_out) {
+        (Some(amount_in), _) => Ok((SwapKind::ExactInput, amount_in)),
+        (None, Some(amount_out)) => Ok((SwapKind::ExactOutput, amount_out)),
+        (None, None) => Err("An input or output amount is required".to_string()),
+    }
+}
+
+/// Resolves a token of a swap route, routing native ETH through WETH.
+async fn resolve_route_token(
+    resolver: &Resolver,
+    token: &str,
+    chain: Chain,
+) -> Result<Address, Box<dyn std::error::Error>> {
+    if is_native(token) {
+        return addressbook::contract_address("weth", chain)
+            .ok_or_else(|| format!("WETH not deployed on {}", chain).into());
+    }
+    resolver.resolve(token, chain).await
+}
+
+/// Share of a liquidity position given by `--percent` or `--liquidity`.
+fn liquidity_share(
+    percent: Option<f64>,
+    liquidity: Option<U256>,
+) -> Result<uniswap::LiquidityShare, String> {
+    match (percent, liquidity) {
+        (Some(percent), _) => {
+            if !(0.01..=100.0).contains(&percent) {
+                return Err(format!("Invalid percentage {}", percent));
+            }
+            Ok(uniswap::LiquidityShare::Bps(
+                (percent * 100.0).round() as u32
+            ))
+        }
+        (None, Some(liquidity)) => Ok(uniswap::LiquidityShare::Amount(liquidity)),
+        (None, None) => Err("Either --percent or --liquidity is required".to_string()),
+    }
+}
+
+fn parse_token_id(token_id: &str) -> Result<U256, String> {
+    U256::from_dec_str(token_id.trim_start_matches('#'))
+        .map_err(|_| format!("Invalid position id {}", token_id))
+}
+
+fn is_native(token: &str) -> bool {
+    token.eq_ignore_ascii_case(NATIVE_TOKEN)
+}
+
+/// Parses an optional network name or chain id.
+fn parse_chain(chain: &Option<String>) -> Result<Option<Chain>, String> {
+    chain
+        .as_deref()
+        .map(|chain| {
+            get_chain_from_string(chain).ok_or_else(|| format!("Unsupported network: {}", chain))
+        })
+        .transpose()
+}
+
 fn load_or_create_whitelist() -> Result<Whitelist, Box<dyn std::error::Error>> {
     let path = get_whitelist_path();
     if path.exists() {
@@ -0,0 +1,151 @@
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::journal::{self, JournalEntry};
+use crate::utils;
+use crate::whitelist::Whitelist;
+
+/// Token argument used to designate the chain's native asset in policies.
+pub const NATIVE_TOKEN: &str = "eth";
+
+const DAILY_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Limits applied to outgoing value on a chain. A policy targets one asset
+/// (`token`, `None` for native ETH) and optionally a single recipient
+/// (`wallet`, `None` for any recipient). Amounts are in the asset's base units.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendingPolicy {
+    pub chain_id: u64,
+    pub token: Option<String>,
+    pub wallet: Option<String>,
+    pub max_per_tx: Option<U256>,
+    pub daily_cap: Option<U256>,
+    pub approval_threshold: Option<U256>,
+}
+
+impl SpendingPolicy {
+    pub fn matches(&self, chain_id: u64, token: Option<Address>, recipient: Address) -> bool {
+        self.chain_id == chain_id
+            && same_address(self.token.as_deref(), token)
+            && self
+                .wallet
+                .as_deref()
+                .is_none_or(|wallet| same_address(Some(wallet), Some(recipient)))
+    }
+
+    pub fn targets(&self, chain_id: u64, token: Option<Address>, wallet: Option<Address>) -> bool {
+        self.chain_id == chain_id
+            && same_address(self.token.as_deref(), token)
+            && same_address(self.wallet.as_deref(), wallet)
+    }
+
+    pub fn describe(&self) -> String {
+        let asset = self.token.as_deref().unwrap_or(NATIVE_TOKEN);
+        match &self.wallet {
+            Some(wallet) => format!("{} to {} on chain {}", asset, wallet, self.chain_id),
+            None => format!("{} on chain {}", asset, self.chain_id),
+        }
+    }
+
+    /// Sums what the journal says was already sent within the last 24 hours
+    /// under the scope of this policy.
+    fn spent_last_24h(&self, entries: &[JournalEntry], now: u64) -> U256 {
+        entries
+            .iter()
+            .filter(|entry| {
+                entry.chain_id == self.chain_id
+                    && entry.timestamp + DAILY_WINDOW_SECS > now
+                    && entry.token.as_deref().map(str::to_lowercase)
+                        == self.token.as_deref().map(str::to_lowercase)
+                    && self
+                        .wallet
+                        .as_deref()
+                        .is_none_or(|wallet| wallet.eq_ignore_ascii_case(&entry.recipient))
+            })
+            .fold(U256::zero(), |total, entry| total + entry.amount)
+    }
+}
+
+fn same_address(stored: Option<&str>, address: Option<Address>) -> bool {
+    match (stored, address) {
+        (None, None) => true,
+        (Some(stored), Some(address)) => {
+            Address::from_str(stored).is_ok_and(|stored| stored == address)
+        }
+        _ => false,
+    }
+}
+
+/// Checks a spend against every matching policy. Returns an error naming the
+/// violated rule, and asks for interactive confirmation when the amount is
+/// above an approval threshold.
+pub fn enforce(
+    whitelist: &Whitelist,
+    chain_id: u64,
+    token: Option<Address>,
+    recipient: Address,
+    amount: U256,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let policies: Vec<&SpendingPolicy> = whitelist
+        .get_spending_policies()
+        .iter()
+        .filter(|policy| policy.matches(chain_id, token, recipient))
+        .collect();
+    if policies.is_empty() {
+        return Ok(());
+    }
+
+    let entries = journal::load_entries()?;
+    let now = utils::unix_now();
+    let show = |value: U256| whitelist.format_amount(token, chain_id, value);
+
+    for policy in policies {
+        if let Some(max_per_tx) = policy.max_per_tx {
+            if amount > max_per_tx {
+                return Err(format!(
+                    "Spending policy violated (max-per-tx) for {}: {} exceeds {}",
+                    policy.describe(),
+                    show(amount),
+                    show(max_per_tx)
+                )
+                .into());
+            }
+        }
+
+        if let Some(daily_cap) = policy.daily_cap {
+            let spent = policy.spent_last_24h(&entries, now);
+            if spent + amount > daily_cap {
+                return Err(format!(
+                    "Spending policy violated (daily-cap) for {}: {} already sent in the last 24h, {} more would exceed {}",
+                    policy.describe(),
+                    show(spent),
+                    show(amount),
+                    show(daily_cap)
+                )
+                .into());
+            }
+        }
+
+        if let Some(threshold) = policy.approval_threshold {
+            if amount > threshold {
+                let prompt = format!(
+                    "Sending {} ({}) exceeds the approval threshold of {}. Approve?",
+                    show(amount),
+                    policy.describe(),
+                    show(threshold)
+                );
+                if !utils::confirm(&prompt)? {
+                    return Err(format!(
+                        "Spending policy violated (approval-threshold) for {}: {} was not approved",
+                        policy.describe(),
+                        show(amount)
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,19 +1,79 @@
 use crate::addressbook;
+use crate::bridge::lifi::LiFiBridge;
+use crate::config::{get_chain_from_string, get_ens_provider};
 use crate::whitelist::Whitelist;
+use ethers::providers::{Http, Middleware, Provider};
 use ethers::types::{Address, Chain};
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 pub struct Resolver {
     whitelist: Arc<Whitelist>,
+    ens_provider: Option<Provider<Http>>,
+    ens_names: Mutex<HashMap<Address, Option<String>>>,
+    use_token_list: bool,
 }
 
 impl Resolver {
     pub fn new(whitelist: Arc<Whitelist>) -> Self {
-        Resolver { whitelist }
+        Resolver {
+            whitelist,
+            ens_provider: get_ens_provider(),
+            ens_names: Mutex::new(HashMap::new()),
+            use_token_list: false,
+        }
+    }
+
+    /// Falls back to the LI.FI token list of the chain for symbols that are
+    /// neither whitelisted nor in the addressbook.
+    pub fn with_token_list(mut self, enabled: bool) -> Self {
+        self.use_token_list = enabled;
+        self
+    }
+
+    /// Resolves `input` on `chain`. A chain-qualified identifier is accepted
+    /// as long as it refers to the same chain.
+    pub async fn resolve(
+        &self,
+        input: &str,
+        chain: Chain,
+    ) -> Result<Address, Box<dyn std::error::Error>> {
+        let (address, _) = self.resolve_with_chain(input, Some(chain)).await?;
+        Ok(address)
+    }
+
+    /// Resolves `input` along with the chain it refers to, taken from a
+    /// `name@chain`, `chainid:address` or `eip155:chainid:address` qualifier,
+    /// or from `default_chain` for unqualified identifiers.
+    pub async fn resolve_with_chain(
+        &self,
+        input: &str,
+        default_chain: Option<Chain>,
+    ) -> Result<(Address, Chain), Box<dyn std::error::Error>> {
+        let (identifier, qualified_chain) = parse_qualified(input)?;
+        let chain = match (qualified_chain, default_chain) {
+            (Some(qualified), Some(default)) if qualified != default => {
+                return Err(format!(
+                    "{} refers to {} but {} was expected",
+                    input, qualified, default
+                )
+                .into())
+            }
+            (Some(chain), _) | (None, Some(chain)) => chain,
+            (None, None) => {
+                return Err(format!(
+                    "No chain given for {}, use {}@<chain> or pass the chain explicitly",
+                    input, input
+                )
+                .into())
+            }
+        };
+        let address = self.resolve_unqualified(identifier, chain).await?;
+        Ok((address, chain))
     }
 
-    pub fn resolve(
+    async fn resolve_unqualified(
         &self,
         input: &str,
         chain: Chain,
@@ -24,15 +84,164 @@ impl Resolver {
         }
 
         // Check if the input is a whitelisted wallet name
-        if let Some(wallet_info) = self.whitelist.get_wallet_by_name(input) {
+        if let Some(wallet_info) = self.whitelist.get_wallet_by_name(input, chain as u64) {
             return Ok(Address::from_str(&wallet_info.address)?);
         }
 
+        // Check if the input is the symbol of a token whitelisted on this chain
+        let tokens = self.whitelist.get_tokens_by_symbol(input, chain as u64);
+        match tokens.as_slice() {
+            [] => {}
+            [token] => return Ok(Address::from_str(&token.address)?),
+            _ => {
+                let candidates = tokens
+                    .iter()
+                    .map(|token| {
+                        format!(
+                            "{} ({})",
+                            token.address,
+                            token.name.as_deref().unwrap_or(&token.symbol)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                return Err(ambiguous_symbol(input, chain, &candidates).into());
+            }
+        }
+
         // Check if the input is a token name in the addressbook
         if let Some(address) = addressbook::contract_address(input, chain) {
             return Ok(address);
         }
 
+        // Check if the input is an ENS name. Whitelist names take precedence so
+        // that a registered ENS name cannot shadow a known wallet.
+        if is_ens_name(input) {
+            let provider = self.ens_provider.as_ref().ok_or_else(|| {
+                format!(
+                    "MAINNET_RPC_URL is not set, cannot resolve ENS name {}",
+                    input
+                )
+            })?;
+            let address = provider
+                .resolve_name(input)
+                .await
+                .map_err(|e| format!("Could not resolve ENS name {}: {}", input, e))?;
+            println!("Resolved {} to {:?}", input, address);
+            return Ok(address);
+        }
+
+        if self.use_token_list {
+            if let Some(address) = self.resolve_from_token_list(input, chain).await? {
+                return Ok(address);
+            }
+        }
+
         Err(format!("Could not resolve address for: {}", input).into())
     }
+
+    async fn resolve_from_token_list(
+        &self,
+        symbol: &str,
+        chain: Chain,
+    ) -> Result<Option<Address>, Box<dyn std::error::Error>> {
+        let tokens = LiFiBridge::new()
+            .get_known_tokens_cached(chain as u64)
+            .await?;
+        let matches: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.symbol.eq_ignore_ascii_case(symbol))
+            .collect();
+        match matches.as_slice() {
+            [] => Ok(None),
+            [token] => {
+                let address = Address::from_str(&token.address)?;
+                println!(
+                    "Resolved {} to {:?} ({}) from the LI.FI token list",
+                    symbol, address, token.name
+                );
+                Ok(Some(address))
+            }
+            _ => {
+                let candidates = matches
+                    .iter()
+                    .map(|token| format!("{} ({})", token.address, token.name))
+                    .collect::<Vec<_>>();
+                Err(ambiguous_symbol(symbol, chain, &candidates).into())
+            }
+        }
+    }
+
+    /// Returns a display name for each address that has one: the whitelist
+    /// name if the wallet is whitelisted on `chain`, otherwise its primary ENS
+    /// name. ENS names are only used when they resolve back to the address.
+    pub async fn lookup_names(
+        &self,
+        addresses: impl IntoIterator<Item = Address>,
+        chain: Chain,
+    ) -> HashMap<Address, String> {
+        let mut names = HashMap::new();
+        for address in addresses {
+            if names.contains_key(&address) {
+                continue;
+            }
+            let name = match self.whitelist.wallet_name(address, chain as u64) {
+                Some(name) => Some(name.to_string()),
+                None => self.lookup_ens_name(address).await,
+            };
+            if let Some(name) = name {
+                names.insert(address, name);
+            }
+        }
+        names
+    }
+
+    async fn lookup_ens_name(&self, address: Address) -> Option<String> {
+        let provider = self.ens_provider.as_ref()?;
+        if let Some(cached) = self.ens_names.lock().unwrap().get(&address) {
+            return cached.clone();
+        }
+        let name = match provider.lookup_address(address).await {
+            Ok(name) => match provider.resolve_name(&name).await {
+                Ok(resolved) if resolved == address => Some(name),
+                _ => None,
+            },
+            Err(_) => None,
+        };
+        self.ens_names.lock().unwrap().insert(address, name.clone());
+        name
+    }
+}
+
+/// Splits a chain-qualified identifier into the identifier and its chain.
+/// Unqualified identifiers are returned as is.
+fn parse_qualified(input: &str) -> Result<(&str, Option<Chain>), String> {
+    let parse_chain = |chain: &str| {
+        get_chain_from_string(chain).ok_or_else(|| format!("Unsupported network: {}", chain))
+    };
+
+    if let Some((identifier, chain)) = input.rsplit_once('@') {
+        return Ok((identifier, Some(parse_chain(chain)?)));
+    }
+
+    let parts: Vec<&str> = input.split(':').collect();
+    match parts.as_slice() {
+        ["eip155", chain_id, address] => Ok((address, Some(parse_chain(chain_id)?))),
+        [chain_id, address] if chain_id.parse::<u64>().is_ok() => {
+            Ok((address, Some(parse_chain(chain_id)?)))
+        }
+        _ => Ok((input, None)),
+    }
+}
+
+fn ambiguous_symbol(symbol: &str, chain: Chain, candidates: &[String]) -> String {
+    format!(
+        "Symbol {} is ambiguous on {}, use one of the addresses instead: {}",
+        symbol,
+        chain,
+        candidates.join(", ")
+    )
+}
+
+fn is_ens_name(input: &str) -> bool {
+    input.contains('.') && !input.starts_with('.') && !input.ends_with('.')
 }
@@ -22,7 +22,7 @@ pub async fn setup_signer(
         .expect("Failed to parse wallet")
         .with_chain_id(chain_id.as_u64());
 
-    if !whitelist.is_wallet_whitelisted(&wallet.address().to_string()) {
+    if !whitelist.is_wallet_whitelisted(wallet.address(), chain_id.as_u64()) {
         panic!("Wallet is not whitelisted");
     }
 
@@ -0,0 +1,316 @@
+//! Uniswap V3 tick and liquidity math, ported from the core `TickMath` and
+//! periphery `LiquidityAmounts` libraries.
+
+use ethers::types::{U256, U512};
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+
+/// Multipliers for each bit of the absolute tick, as Q128 values of
+/// `1 / sqrt(1.0001) ^ (2 ^ bit)`.
+const TICK_MULTIPLIERS: [&str; 19] = [
+    "fff97272373d413259a46990580e213a",
+    "fff2e50f5f656932ef12357cf3c7fdcc",
+    "ffe5caca7e10e4e61c3624eaa0941cd0",
+    "ffcb9843d60f6159c9db58835c926644",
+    "ff973b41fa98c081472e6896dfb254c0",
+    "ff2ea16466c96a3843ec78b326b52861",
+    "fe5dee046a99a2a811c461f1969c3053",
+    "fcbe86c7900a88aedcffc83b479aa3a4",
+    "f987a7253ac413176f2b074cf7815e54",
+    "f3392b0822b70005940c7a398e4b70f3",
+    "e7159475a2c29b7443b29c7fa6e889d9",
+    "d097f3bdfd2022b8845ad8f792aa5825",
+    "a9f746462d870fdf8a65dc1f90e061e5",
+    "70d869a156d2a1b890bb3df62baf32f7",
+    "31be135f97d08fd981231505542fcfa6",
+    "9aa508b5b7a84e1c677de54f3e99bc9",
+    "5d6af8dedb81196699c329225ee604",
+    "2216e584f5fa1ea926041bedfe98",
+    "48a170391f7dc42444e8fa2",
+];
+
+fn q96() -> U256 {
+    U256::one() << 96
+}
+
+/// `a * b / denominator` without overflowing the intermediate product.
+fn mul_div(a: U256, b: U256, denominator: U256) -> U256 {
+    let result = a.full_mul(b) / U512::from(denominator);
+    U256::try_from(result).unwrap_or(U256::MAX)
+}
+
+/// The Q64.96 square root price at `tick`, exactly as computed on-chain.
+pub fn sqrt_ratio_at_tick(tick: i32) -> Result<U256, String> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(format!("Tick {} is out of range", tick));
+    }
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from_str_radix("fffcb933bd6fad37aa2d162d1a594001", 16).unwrap()
+    } else {
+        U256::one() << 128
+    };
+    for (bit, multiplier) in TICK_MULTIPLIERS.iter().enumerate() {
+        if abs_tick & (2 << bit) != 0 {
+            ratio = (ratio * U256::from_str_radix(multiplier, 16).unwrap()) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Round up when going from Q128.128 to Q64.96
+    let round_up = !(ratio % (U256::one() << 32)).is_zero();
+    Ok((ratio >> 32) + U256::from(round_up as u8))
+}
+
+/// Price of token0 in token1, adjusted for decimals, at `tick`.
+pub fn tick_to_price(tick: i32, decimals0: u8, decimals1: u8) -> f64 {
+    1.0001f64.powi(tick) * 10f64.powi(decimals0 as i32 - decimals1 as i32)
+}
+
+/// Price of token0 in token1, adjusted for decimals, at a Q64.96 square root
+/// price.
+pub fn sqrt_price_to_price(sqrt_price_x96: U256, decimals0: u8, decimals1: u8) -> f64 {
+    let sqrt_price: f64 = sqrt_price_x96.to_string().parse().unwrap_or(f64::MAX) / 2f64.powi(96);
+    sqrt_price * sqrt_price * 10f64.powi(decimals0 as i32 - decimals1 as i32)
+}
+
+/// The tick closest to a price of token0 in token1, rounded to a multiple of
+/// `tick_spacing` within the usable range.
+pub fn price_to_tick(
+    price: f64,
+    decimals0: u8,
+    decimals1: u8,
+    tick_spacing: i32,
+) -> Result<i32, String> {
+    if !(price.is_finite() && price > 0.0) {
+        return Err(format!("Invalid price {}", price));
+    }
+    let raw_price = price * 10f64.powi(decimals1 as i32 - decimals0 as i32);
+    let tick = raw_price.ln() / 1.0001f64.ln();
+    let spacing = tick_spacing as f64;
+    let tick = ((tick / spacing).round() * spacing) as i32;
+    Ok(tick.clamp(
+        MIN_TICK / tick_spacing * tick_spacing,
+        MAX_TICK / tick_spacing * tick_spacing,
+    ))
+}
+
+fn liquidity_for_amount0(sqrt_a: U256, sqrt_b: U256, amount0: U256) -> U256 {
+    let intermediate = mul_div(sqrt_a, sqrt_b, q96());
+    mul_div(amount0, intermediate, sqrt_b - sqrt_a)
+}
+
+fn liquidity_for_amount1(sqrt_a: U256, sqrt_b: U256, amount1: U256) -> U256 {
+    mul_div(amount1, q96(), sqrt_b - sqrt_a)
+}
+
+fn to_u128(liquidity: U256) -> u128 {
+    if liquidity > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        liquidity.as_u128()
+    }
+}
+
+/// The most liquidity that `amount0` and `amount1` can provide in the range
+/// between `sqrt_a` and `sqrt_b` at the current price `sqrt_price`.
+pub fn liquidity_for_amounts(
+    sqrt_price: U256,
+    sqrt_a: U256,
+    sqrt_b: U256,
+    amount0: U256,
+    amount1: U256,
+) -> u128 {
+    let liquidity = if sqrt_price <= sqrt_a {
+        liquidity_for_amount0(sqrt_a, sqrt_b, amount0)
+    } else if sqrt_price < sqrt_b {
+        liquidity_for_amount0(sqrt_price, sqrt_b, amount0)
+            .min(liquidity_for_amount1(sqrt_a, sqrt_price, amount1))
+    } else {
+        liquidity_for_amount1(sqrt_a, sqrt_b, amount1)
+    };
+    to_u128(liquidity)
+}
+
+/// Amount of token0 that `liquidity` holds between `sqrt_a` and `sqrt_b`,
+/// with `sqrt_a` below `sqrt_b`.
+pub fn amount0_for_liquidity(sqrt_a: U256, sqrt_b: U256, liquidity: u128) -> U256 {
+    mul_div(U256::from(liquidity) << 96, sqrt_b - sqrt_a, sqrt_b) / sqrt_a
+}
+
+/// Amount of token1 that `liquidity` holds between `sqrt_a` and `sqrt_b`,
+/// with `sqrt_a` below `sqrt_b`.
+pub fn amount1_for_liquidity(sqrt_a: U256, sqrt_b: U256, liquidity: u128) -> U256 {
+    mul_div(U256::from(liquidity), sqrt_b - sqrt_a, q96())
+}
+
+/// Token amounts represented by `liquidity` in the range between `sqrt_a`
+/// and `sqrt_b` at the current price `sqrt_price`.
+pub fn amounts_for_liquidity(
+    sqrt_price: U256,
+    sqrt_a: U256,
+    sqrt_b: U256,
+    liquidity: u128,
+) -> (U256, U256) {
+    if sqrt_price <= sqrt_a {
+        (
+            amount0_for_liquidity(sqrt_a, sqrt_b, liquidity),
+            U256::zero(),
+        )
+    } else if sqrt_price < sqrt_b {
+        (
+            amount0_for_liquidity(sqrt_price, sqrt_b, liquidity),
+            amount1_for_liquidity(sqrt_a, sqrt_price, liquidity),
+        )
+    } else {
+        (
+            U256::zero(),
+            amount1_for_liquidity(sqrt_a, sqrt_b, liquidity),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_SQRT_RATIO: &str = "4295128739";
+    const MAX_SQRT_RATIO: &str = "1461446703485210103287273052203988822378723970342";
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    /// Liquidity and the amounts it represents are rounded down, so amounts
+    /// read back may fall short of the deposit by a few wei.
+    fn assert_close(actual: U256, expected: U256) {
+        let difference = if actual > expected {
+            actual - expected
+        } else {
+            expected - actual
+        };
+        assert!(
+            difference <= U256::from(2),
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn sqrt_ratio_at_tick_zero_is_one() {
+        assert_eq!(sqrt_ratio_at_tick(0).unwrap(), q96());
+    }
+
+    #[test]
+    fn sqrt_ratio_at_tick_bounds() {
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK).unwrap(), dec(MIN_SQRT_RATIO));
+        assert_eq!(sqrt_ratio_at_tick(MAX_TICK).unwrap(), dec(MAX_SQRT_RATIO));
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK + 1).unwrap(), dec("4295343490"));
+        assert_eq!(
+            sqrt_ratio_at_tick(MAX_TICK - 1).unwrap(),
+            dec("1461373636630004318706518188784493106690254656249")
+        );
+        assert!(sqrt_ratio_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn sqrt_ratio_at_mid_ticks() {
+        assert_eq!(
+            sqrt_ratio_at_tick(1).unwrap(),
+            dec("79232123823359799118286999568")
+        );
+        assert_eq!(
+            sqrt_ratio_at_tick(-1).unwrap(),
+            dec("79224201403219477170569942574")
+        );
+        for tick in [-200_000, -50_000, 12_345, 100_000] {
+            let exact: f64 = sqrt_ratio_at_tick(tick)
+                .unwrap()
+                .to_string()
+                .parse()
+                .unwrap();
+            let expected = 1.0001f64.powf(tick as f64 / 2.0) * 2f64.powi(96);
+            assert!(
+                ((exact - expected) / expected).abs() < 1e-9,
+                "tick {}",
+                tick
+            );
+        }
+    }
+
+    #[test]
+    fn price_and_tick_round_trip() {
+        // WETH (18 decimals) priced in USDC (6 decimals)
+        for tick in [-201_840, -199_980, -195_000] {
+            let price = tick_to_price(tick, 18, 6);
+            assert_eq!(price_to_tick(price, 18, 6, 60).unwrap(), tick);
+        }
+        let tick = price_to_tick(3000.0, 18, 6, 10).unwrap();
+        assert_eq!(tick % 10, 0);
+        assert!((tick_to_price(tick, 18, 6) / 3000.0 - 1.0).abs() < 0.001);
+        assert!(
+            (sqrt_price_to_price(sqrt_ratio_at_tick(tick).unwrap(), 18, 6)
+                / tick_to_price(tick, 18, 6)
+                - 1.0)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            price_to_tick(1e-300, 18, 18, 60).unwrap(),
+            MIN_TICK / 60 * 60
+        );
+        assert!(price_to_tick(0.0, 18, 18, 60).is_err());
+    }
+
+    #[test]
+    fn liquidity_below_range_is_token0_only() {
+        let (sqrt_a, sqrt_b) = (
+            sqrt_ratio_at_tick(-600).unwrap(),
+            sqrt_ratio_at_tick(600).unwrap(),
+        );
+        let sqrt_price = sqrt_ratio_at_tick(-1200).unwrap();
+        let amount0 = U256::exp10(18);
+        let liquidity = liquidity_for_amounts(sqrt_price, sqrt_a, sqrt_b, amount0, U256::exp10(18));
+        let (out0, out1) = amounts_for_liquidity(sqrt_price, sqrt_a, sqrt_b, liquidity);
+        assert_close(out0, amount0);
+        assert!(out0 <= amount0);
+        assert!(out1.is_zero());
+    }
+
+    #[test]
+    fn liquidity_above_range_is_token1_only() {
+        let (sqrt_a, sqrt_b) = (
+            sqrt_ratio_at_tick(-600).unwrap(),
+            sqrt_ratio_at_tick(600).unwrap(),
+        );
+        let sqrt_price = sqrt_ratio_at_tick(1200).unwrap();
+        let amount1 = U256::exp10(18);
+        let liquidity = liquidity_for_amounts(sqrt_price, sqrt_a, sqrt_b, U256::exp10(18), amount1);
+        let (out0, out1) = amounts_for_liquidity(sqrt_price, sqrt_a, sqrt_b, liquidity);
+        assert!(out0.is_zero());
+        assert_close(out1, amount1);
+        assert!(out1 <= amount1);
+    }
+
+    #[test]
+    fn liquidity_in_range_is_limited_by_the_scarcer_token() {
+        let (sqrt_a, sqrt_b) = (
+            sqrt_ratio_at_tick(-600).unwrap(),
+            sqrt_ratio_at_tick(600).unwrap(),
+        );
+        let sqrt_price = q96();
+        // The range is symmetric around a price of 1, so both tokens are
+        // needed in equal amounts and token1 is the scarcer one here
+        let (amount0, amount1) = (U256::exp10(18) * 2, U256::exp10(18));
+        let liquidity = liquidity_for_amounts(sqrt_price, sqrt_a, sqrt_b, amount0, amount1);
+        let (out0, out1) = amounts_for_liquidity(sqrt_price, sqrt_a, sqrt_b, liquidity);
+        assert_close(out1, amount1);
+        assert!(out1 <= amount1);
+        assert!(out0 < amount0);
+        assert_close(out0, out1);
+    }
+}
@@ -0,0 +1,984 @@
+use ethers::prelude::*;
+use ethers::types::{Address, Bytes};
+use std::sync::Arc;
+
+use crate::bindings::uniswap_v3_pool::SwapFilter;
+use crate::bindings::{
+    UNISWAP_V2_FACTORY, UNISWAP_V2_POOL, UNISWAP_V2_ROUTER, UNISWAP_V3_FACTORY, UNISWAP_V3_POOL,
+    UNISWAP_V3_POSITION_MANAGER, UNISWAP_V3_QUOTER_V2,
+};
+use crate::{approval, tick_math};
+
+/// Fee tiers enabled on the canonical Uniswap V3 factories, in hundredths of
+/// a basis point.
+pub const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+
+/// Fee charged by every Uniswap V2 pair, in the same unit as V3 fee tiers.
+pub const V2_FEE: u32 = 3000;
+
+/// Gas used by a single-hop V2 swap, which the router does not estimate.
+pub const V2_SWAP_GAS: u64 = 120_000;
+
+/// Gas a V3 router swap spends outside the pool, which QuoterV2 leaves out
+/// of its estimate: the 21k transaction base plus the router call and the
+/// two token transfers.
+pub const V3_SWAP_OVERHEAD_GAS: u64 = 60_000;
+
+/// Slippage tolerance used when no minimum output is given, in basis points.
+pub const DEFAULT_SLIPPAGE_BPS: u32 = 50;
+
+const BPS: u32 = 10_000;
+const FEE_DENOMINATOR: f64 = 1_000_000.0;
+
+/// Largest value a `uint24` fee can hold.
+const MAX_FEE: u32 = (1 << 24) - 1;
+
+/// A Uniswap V3 swap path: `tokens[i]` is swapped for `tokens[i + 1]` in the
+/// pool with fee `fees[i]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapPath {
+    pub tokens: Vec<Address>,
+    pub fees: Vec<u32>,
+}
+
+impl SwapPath {
+    pub fn new(tokens: Vec<Address>, fees: Vec<u32>) -> Result<Self, String> {
+        if tokens.len() < 2 || fees.len() != tokens.len() - 1 {
+            return Err(format!(
+                "A path needs one fee between each pair of tokens, got {} tokens and {} fees",
+                tokens.len(),
+                fees.len()
+            ));
+        }
+        if let Some(fee) = fees.iter().find(|fee| **fee > MAX_FEE) {
+            return Err(format!("Fee {} does not fit in a uint24", fee));
+        }
+        Ok(SwapPath { tokens, fees })
+    }
+
+    pub fn single(token_in: Address, token_out: Address, fee: u32) -> Self {
+        SwapPath {
+            tokens: vec![token_in, token_out],
+            fees: vec![fee],
+        }
+    }
+
+    pub fn token_in(&self) -> Address {
+        self.tokens[0]
+    }
+
+    pub fn token_out(&self) -> Address {
+        self.tokens[self.tokens.len() - 1]
+    }
+
+    /// The `(token_in, token_out, fee)` of every hop.
+    pub fn hops(&self) -> impl Iterator<Item = (Address, Address, u32)> + '_ {
+        self.tokens
+            .windows(2)
+            .zip(&self.fees)
+            .map(|(pair, fee)| (pair[0], pair[1], *fee))
+    }
+
+    /// Packed `address | uint24 | address | ...` encoding expected by the
+    /// router's `exactInput`.
+    pub fn encode(&self) -> Bytes {
+        let mut path = Vec::with_capacity(20 + self.fees.len() * 23);
+        path.extend_from_slice(self.tokens[0].as_bytes());
+        for (token, fee) in self.tokens[1..].iter().zip(&self.fees) {
+            path.extend_from_slice(&fee.to_be_bytes()[1..]);
+            path.extend_from_slice(token.as_bytes());
+        }
+        Bytes::from(path)
+    }
+
+    /// Encoding expected by `exactOutput` and `quoteExactOutput`, which walk
+    /// the path from the output token back to the input token.
+    pub fn encode_reversed(&self) -> Bytes {
+        let mut tokens = self.tokens.clone();
+        tokens.reverse();
+        let mut fees = self.fees.clone();
+        fees.reverse();
+        SwapPath { tokens, fees }.encode()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Version {
+    V2,
+    V3,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::V2 => write!(f, "Uniswap V2"),
+            Version::V3 => write!(f, "Uniswap V3"),
+        }
+    }
+}
+
+/// A swap route through either V2 pairs or V3 pools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    V2(Vec<Address>),
+    V3(SwapPath),
+}
+
+impl Route {
+    pub fn tokens(&self) -> &[Address] {
+        match self {
+            Route::V2(tokens) => tokens,
+            Route::V3(path) => &path.tokens,
+        }
+    }
+
+    pub fn token_in(&self) -> Address {
+        self.tokens()[0]
+    }
+
+    pub fn token_out(&self) -> Address {
+        self.tokens()[self.tokens().len() - 1]
+    }
+}
+
+/// One hop of a quoted route.
+#[derive(Debug, Clone)]
+pub struct QuotedHop {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub pool: Address,
+    pub fee: u32,
+    /// Raw units of `token_out` per raw unit of `token_in` at the pool's
+    /// current price, before fees.
+    pub mid_price: f64,
+}
+
+/// Which side of a swap is fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    ExactInput,
+    ExactOutput,
+}
+
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub version: Version,
+    pub kind: SwapKind,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub hops: Vec<QuotedHop>,
+    /// Gas used by the swap as estimated by the V3 quoter.
+    pub gas_estimate: Option<U256>,
+}
+
+impl Quote {
+    /// Share of the output lost to the swap moving the pool prices, i.e. the
+    /// shortfall against trading `amount_in` at the current mid prices net
+    /// of pool fees.
+    pub fn price_impact(&self) -> f64 {
+        let ideal_out = self
+            .hops
+            .iter()
+            .fold(to_f64(self.amount_in), |amount, hop| {
+                amount * hop.mid_price * (1.0 - hop.fee as f64 / FEE_DENOMINATOR)
+            });
+        if ideal_out <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - to_f64(self.amount_out) / ideal_out).max(0.0)
+    }
+
+    pub fn minimum_out(&self, slippage_bps: u32) -> U256 {
+        minimum_out(self.amount_out, slippage_bps)
+    }
+
+    pub fn maximum_in(&self, slippage_bps: u32) -> U256 {
+        maximum_in(self.amount_in, slippage_bps)
+    }
+}
+
+/// `amount` reduced by a slippage tolerance of `slippage_bps` basis points.
+pub fn minimum_out(amount: U256, slippage_bps: u32) -> U256 {
+    amount * U256::from(BPS.saturating_sub(slippage_bps)) / U256::from(BPS)
+}
+
+/// `amount` increased by a slippage tolerance of `slippage_bps` basis points.
+pub fn maximum_in(amount: U256, slippage_bps: u32) -> U256 {
+    amount * U256::from(BPS + slippage_bps) / U256::from(BPS)
+}
+
+/// Formats basis points as a percentage, e.g. 50 as `0.5%`.
+pub fn format_bps(bps: u32) -> String {
+    format!("{}%", bps as f64 / 100.0)
+}
+
+pub fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// Raw units of `token_out` per raw unit of `token_in` given the price of
+/// token0 in token1. Pools order their tokens by address.
+fn directional_price(token_in: Address, token_out: Address, price0: f64) -> f64 {
+    if token_in < token_out {
+        price0
+    } else {
+        1.0 / price0
+    }
+}
+
+/// Quotes an exact input swap along `path` with the V3 QuoterV2, which
+/// simulates the swap against the current state of every pool.
+pub async fn quote_v3<M: Middleware + 'static>(
+    quoter: &UNISWAP_V3_QUOTER_V2<M>,
+    factory: &UNISWAP_V3_FACTORY<M>,
+    provider: Arc<M>,
+    path: &SwapPath,
+    amount_in: U256,
+) -> Result<Quote, Box<dyn std::error::Error>> {
+    let hops = v3_hops(factory, provider, path).await?;
+    let (amount_out, _, _, gas_estimate) = quoter
+        .quote_exact_input(path.encode(), amount_in)
+        .call()
+        .await
+        .map_err(|e| format!("Uniswap V3 quote failed: {}", e))?;
+    Ok(Quote {
+        version: Version::V3,
+        kind: SwapKind::ExactInput,
+        amount_in,
+        amount_out,
+        hops,
+        gas_estimate: Some(gas_estimate),
+    })
+}
+
+/// Quotes the input needed to receive exactly `amount_out` along `path`.
+pub async fn quote_v3_exact_output<M: Middleware + 'static>(
+    quoter: &UNISWAP_V3_QUOTER_V2<M>,
+    factory: &UNISWAP_V3_FACTORY<M>,
+    provider: Arc<M>,
+    path: &SwapPath,
+    amount_out: U256,
+) -> Result<Quote, Box<dyn std::error::Error>> {
+    let hops = v3_hops(factory, provider, path).await?;
+    let (amount_in, _, _, gas_estimate) = quoter
+        .quote_exact_output(path.encode_reversed(), amount_out)
+        .call()
+        .await
+        .map_err(|e| format!("Uniswap V3 quote failed: {}", e))?;
+    Ok(Quote {
+        version: Version::V3,
+        kind: SwapKind::ExactOutput,
+        amount_in,
+        amount_out,
+        hops,
+        gas_estimate: Some(gas_estimate),
+    })
+}
+
+/// Looks up the pool and mid price of every hop of a V3 path.
+async fn v3_hops<M: Middleware + 'static>(
+    factory: &UNISWAP_V3_FACTORY<M>,
+    provider: Arc<M>,
+    path: &SwapPath,
+) -> Result<Vec<QuotedHop>, Box<dyn std::error::Error>> {
+    let mut hops = Vec::new();
+    for (token_in, token_out, fee) in path.hops() {
+        let pool = get_pool(factory, token_in, token_out, fee)
+            .await?
+            .ok_or_else(|| {
+                format!(
+                    "No {} Uniswap V3 pool for {:?} and {:?}",
+                    format_fee(fee),
+                    token_in,
+                    token_out
+                )
+            })?;
+        let (sqrt_price_x96, ..) = UNISWAP_V3_POOL::new(pool, Arc::clone(&provider))
+            .slot_0()
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        let price0 = (to_f64(sqrt_price_x96) / 2f64.powi(96)).powi(2);
+        hops.push(QuotedHop {
+            token_in,
+            token_out,
+            pool,
+            fee,
+            mid_price: directional_price(token_in, token_out, price0),
+        });
+    }
+    Ok(hops)
+}
+
+/// Amounts actually paid into the first pool and received from the last
+/// pool of a V3 swap, decoded from the pools' `Swap` logs in `receipt`.
+pub fn swapped_amounts(receipt: &TransactionReceipt, hops: &[QuotedHop]) -> Option<(U256, U256)> {
+    let first = hops.first()?;
+    let last = hops.last()?;
+    let amount_in = pool_delta(receipt, first.pool, first.token_in < first.token_out)?;
+    let amount_out = pool_delta(receipt, last.pool, last.token_out < last.token_in)?;
+    Some((amount_in.unsigned_abs(), amount_out.unsigned_abs()))
+}
+
+/// The pool's balance change of token0 (or token1) in its `Swap` log:
+/// positive when the pool received the token.
+fn pool_delta(receipt: &TransactionReceipt, pool: Address, token0: bool) -> Option<I256> {
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == pool)
+        .find_map(|log| ethers::contract::parse_log::<SwapFilter>(log.clone()).ok())
+        .map(|swap| if token0 { swap.amount_0 } else { swap.amount_1 })
+}
+
+/// Quotes an exact input swap through the V2 pairs of `tokens` with the
+/// router's `getAmountsOut`.
+pub async fn quote_v2<M: Middleware + 'static>(
+    router: &UNISWAP_V2_ROUTER<M>,
+    factory: &UNISWAP_V2_FACTORY<M>,
+    provider: Arc<M>,
+    tokens: &[Address],
+    amount_in: U256,
+) -> Result<Quote, Box<dyn std::error::Error>> {
+    let hops = v2_hops(factory, provider, tokens).await?;
+    let amounts = router
+        .get_amounts_out(amount_in, tokens.to_vec())
+        .call()
+        .await
+        .map_err(|e| format!("Uniswap V2 quote failed: {}", e))?;
+    let amount_out = *amounts
+        .last()
+        .ok_or("Uniswap V2 quote returned no amounts")?;
+    Ok(Quote {
+        version: Version::V2,
+        kind: SwapKind::ExactInput,
+        amount_in,
+        amount_out,
+        hops,
+        gas_estimate: None,
+    })
+}
+
+/// Quotes an exact output swap through the V2 pairs of `tokens` with the
+/// router's `getAmountsIn`.
+pub async fn quote_v2_exact_output<M: Middleware + 'static>(
+    router: &UNISWAP_V2_ROUTER<M>,
+    factory: &UNISWAP_V2_FACTORY<M>,
+    provider: Arc<M>,
+    tokens: &[Address],
+    amount_out: U256,
+) -> Result<Quote, Box<dyn std::error::Error>> {
+    let hops = v2_hops(factory, provider, tokens).await?;
+    let amounts = router
+        .get_amounts_in(amount_out, tokens.to_vec())
+        .call()
+        .await
+        .map_err(|e| format!("Uniswap V2 quote failed: {}", e))?;
+    let amount_in = *amounts
+        .first()
+        .ok_or("Uniswap V2 quote returned no amounts")?;
+    Ok(Quote {
+        version: Version::V2,
+        kind: SwapKind::ExactOutput,
+        amount_in,
+        amount_out,
+        hops,
+        gas_estimate: None,
+    })
+}
+
+/// Looks up the pair and mid price of every hop of a V2 route.
+async fn v2_hops<M: Middleware + 'static>(
+    factory: &UNISWAP_V2_FACTORY<M>,
+    provider: Arc<M>,
+    tokens: &[Address],
+) -> Result<Vec<QuotedHop>, Box<dyn std::error::Error>> {
+    let mut hops = Vec::new();
+    for pair in tokens.windows(2) {
+        let (token_in, token_out) = (pair[0], pair[1]);
+        let pool = factory
+            .get_pair(token_in, token_out)
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        if pool.is_zero() {
+            return Err(
+                format!("No Uniswap V2 pair for {:?} and {:?}", token_in, token_out).into(),
+            );
+        }
+        let (reserve0, reserve1, _) = UNISWAP_V2_POOL::new(pool, Arc::clone(&provider))
+            .get_reserves()
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        if reserve0 == 0 || reserve1 == 0 {
+            return Err(format!("Uniswap V2 pair {:?} has no liquidity", pool).into());
+        }
+        hops.push(QuotedHop {
+            token_in,
+            token_out,
+            pool,
+            fee: V2_FEE,
+            mid_price: directional_price(token_in, token_out, reserve1 as f64 / reserve0 as f64),
+        });
+    }
+    Ok(hops)
+}
+
+/// How much of a liquidity position to withdraw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidityShare {
+    /// A share of the position, in basis points.
+    Bps(u32),
+    /// An exact amount of LP tokens.
+    Amount(U256),
+}
+
+impl LiquidityShare {
+    /// LP tokens to withdraw from a position of `balance` LP tokens.
+    pub fn of(&self, balance: U256) -> Result<U256, String> {
+        let liquidity = match *self {
+            LiquidityShare::Bps(bps) => balance * U256::from(bps) / U256::from(BPS),
+            LiquidityShare::Amount(amount) if amount > balance => {
+                return Err(format!(
+                    "Cannot withdraw {} liquidity, the position holds {}",
+                    amount, balance
+                ))
+            }
+            LiquidityShare::Amount(amount) => amount,
+        };
+        if liquidity.is_zero() {
+            return Err("Nothing to withdraw".to_string());
+        }
+        Ok(liquidity)
+    }
+}
+
+/// Digest of an EIP-2612 permit letting `spender` move `value` LP tokens of
+/// `owner`, built from the pair's own domain separator and type hash.
+pub async fn v2_permit_digest<M: Middleware + 'static>(
+    pair: &UNISWAP_V2_POOL<M>,
+    owner: Address,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+) -> Result<H256, Box<dyn std::error::Error>> {
+    let domain_separator = pair.domain_separator().call().await?;
+    let typehash = pair.permit_typehash().call().await?;
+    let nonce = pair.nonces(owner).call().await?;
+    Ok(approval::permit_digest(
+        domain_separator,
+        typehash,
+        owner,
+        spender,
+        value,
+        nonce,
+        deadline,
+    ))
+}
+
+/// Current price, tick and in-range liquidity of a V3 pool.
+#[derive(Debug, Clone)]
+pub struct PoolState {
+    pub address: Address,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    pub tick_spacing: i32,
+}
+
+pub async fn pool_state<M: Middleware + 'static>(
+    pool: &UNISWAP_V3_POOL<M>,
+) -> Result<PoolState, Box<dyn std::error::Error>> {
+    let (sqrt_price_x96, tick, ..) = pool.slot_0().call().await.map_err(|e| e.to_string())?;
+    let liquidity = pool.liquidity().call().await.map_err(|e| e.to_string())?;
+    let tick_spacing = pool
+        .tick_spacing()
+        .call()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(PoolState {
+        address: pool.address(),
+        sqrt_price_x96,
+        tick,
+        liquidity,
+        tick_spacing,
+    })
+}
+
+/// A price range, either as pool ticks or as prices of the first token in
+/// the second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceRange {
+    Ticks(i32, i32),
+    Prices(f64, f64),
+}
+
+/// A liquidity position held by the NonfungiblePositionManager.
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside0_last: U256,
+    pub fee_growth_inside1_last: U256,
+    pub tokens_owed0: u128,
+    pub tokens_owed1: u128,
+}
+
+impl Position {
+    pub fn in_range(&self, tick: i32) -> bool {
+        self.tick_lower <= tick && tick < self.tick_upper
+    }
+
+    /// Token amounts of the position's liquidity at `sqrt_price_x96`.
+    pub fn amounts(&self, sqrt_price_x96: U256) -> Result<(U256, U256), String> {
+        range_amounts(
+            sqrt_price_x96,
+            self.tick_lower,
+            self.tick_upper,
+            self.liquidity,
+        )
+    }
+
+    /// Fees owed to the position, including those earned since the pool
+    /// last credited it, computed from the pool's fee growth like the pool
+    /// does on its next update.
+    pub async fn uncollected_fees<M: Middleware + 'static>(
+        &self,
+        pool: &UNISWAP_V3_POOL<M>,
+        tick: i32,
+    ) -> Result<(U256, U256), Box<dyn std::error::Error>> {
+        let global0 = pool
+            .fee_growth_global_0x128()
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        let global1 = pool
+            .fee_growth_global_1x128()
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        let (_, _, lower_outside0, lower_outside1, ..) = pool
+            .ticks(self.tick_lower)
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        let (_, _, upper_outside0, upper_outside1, ..) = pool
+            .ticks(self.tick_upper)
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Fee growth counters are allowed to overflow, hence the wrapping
+        // arithmetic
+        let inside = |global: U256, lower_outside: U256, upper_outside: U256| {
+            let below = if tick >= self.tick_lower {
+                lower_outside
+            } else {
+                global.overflowing_sub(lower_outside).0
+            };
+            let above = if tick < self.tick_upper {
+                upper_outside
+            } else {
+                global.overflowing_sub(upper_outside).0
+            };
+            global.overflowing_sub(below).0.overflowing_sub(above).0
+        };
+        let earned = |inside: U256, last: U256| {
+            let growth = inside.overflowing_sub(last).0;
+            let fees = growth.full_mul(U256::from(self.liquidity)) >> 128;
+            U256::try_from(fees).unwrap_or(U256::MAX)
+        };
+        let inside0 = inside(global0, lower_outside0, upper_outside0);
+        let inside1 = inside(global1, lower_outside1, upper_outside1);
+        Ok((
+            U256::from(self.tokens_owed0) + earned(inside0, self.fee_growth_inside0_last),
+            U256::from(self.tokens_owed1) + earned(inside1, self.fee_growth_inside1_last),
+        ))
+    }
+}
+
+/// Token amounts of `liquidity` between two ticks at `sqrt_price_x96`.
+pub fn range_amounts(
+    sqrt_price_x96: U256,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<(U256, U256), String> {
+    Ok(tick_math::amounts_for_liquidity(
+        sqrt_price_x96,
+        tick_math::sqrt_ratio_at_tick(tick_lower)?,
+        tick_math::sqrt_ratio_at_tick(tick_upper)?,
+        liquidity,
+    ))
+}
+
+pub async fn position<M: Middleware + 'static>(
+    manager: &UNISWAP_V3_POSITION_MANAGER<M>,
+    token_id: U256,
+) -> Result<Position, Box<dyn std::error::Error>> {
+    let (
+        _,
+        _,
+        token0,
+        token1,
+        fee,
+        tick_lower,
+        tick_upper,
+        liquidity,
+        fee_growth_inside0_last,
+        fee_growth_inside1_last,
+        owed0,
+        owed1,
+    ) = manager
+        .positions(token_id)
+        .call()
+        .await
+        .map_err(|e| format!("Could not read position #{}: {}", token_id, e))?;
+    Ok(Position {
+        token0,
+        token1,
+        fee,
+        tick_lower,
+        tick_upper,
+        liquidity,
+        fee_growth_inside0_last,
+        fee_growth_inside1_last,
+        tokens_owed0: owed0,
+        tokens_owed1: owed1,
+    })
+}
+
+/// An initialized tick of a V3 pool, where liquidity is added or removed.
+#[derive(Debug, Clone)]
+pub struct InitializedTick {
+    pub tick: i32,
+    pub liquidity_gross: u128,
+    /// Liquidity added when the price crosses the tick upwards.
+    pub liquidity_net: i128,
+}
+
+/// The initialized ticks within `span` ticks of `tick`, in ascending order,
+/// read from the pool's tick bitmap.
+pub async fn initialized_ticks<M: Middleware + 'static>(
+    pool: &UNISWAP_V3_POOL<M>,
+    tick: i32,
+    tick_spacing: i32,
+    span: i32,
+) -> Result<Vec<InitializedTick>, Box<dyn std::error::Error>> {
+    // Each bitmap word covers 256 compressed ticks
+    let word = |tick: i32| (tick.div_euclid(tick_spacing) >> 8) as i16;
+    let first_word = word((tick - span).max(tick_math::MIN_TICK));
+    let last_word = word((tick + span).min(tick_math::MAX_TICK));
+
+    let mut ticks = Vec::new();
+    for word_position in first_word..=last_word {
+        let bitmap = pool
+            .tick_bitmap(word_position)
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        for bit in (0..256).filter(|bit| bitmap.bit(*bit)) {
+            let initialized = ((word_position as i32) * 256 + bit as i32) * tick_spacing;
+            if (initialized - tick).abs() > span {
+                continue;
+            }
+            let (liquidity_gross, liquidity_net, ..) = pool
+                .ticks(initialized)
+                .call()
+                .await
+                .map_err(|e| e.to_string())?;
+            ticks.push(InitializedTick {
+                tick: initialized,
+                liquidity_gross,
+                liquidity_net,
+            });
+        }
+    }
+    Ok(ticks)
+}
+
+/// Amounts swapped in (fee included) and out to move the price of token0
+/// in token1 by `price_change` (e.g. 0.01 for +1%, -0.05 for -5%), walking
+/// the pool's liquidity across `ticks`. Moving the price up sells token1
+/// for token0, moving it down sells token0 for token1.
+pub fn depth(
+    pool: &PoolState,
+    ticks: &[InitializedTick],
+    fee: u32,
+    price_change: f64,
+) -> Result<(U256, U256), String> {
+    const SCALE: u128 = 1_000_000_000_000_000_000;
+    let factor = ((1.0 + price_change).sqrt() * SCALE as f64) as u128;
+    let target = pool.sqrt_price_x96 * U256::from(factor) / U256::from(SCALE);
+    let up = price_change > 0.0;
+
+    let crossed: Vec<&InitializedTick> = if up {
+        ticks.iter().filter(|t| t.tick > pool.tick).collect()
+    } else {
+        ticks.iter().rev().filter(|t| t.tick <= pool.tick).collect()
+    };
+    let (mut amount0, mut amount1) = (U256::zero(), U256::zero());
+    let mut price = pool.sqrt_price_x96;
+    let mut liquidity = pool.liquidity;
+    for next in crossed
+        .iter()
+        .map(|t| Some(*t))
+        .chain(std::iter::once(None))
+    {
+        let boundary = match next {
+            Some(t) => tick_math::sqrt_ratio_at_tick(t.tick)?,
+            None => target,
+        };
+        let reached = (up && boundary >= target) || (!up && boundary <= target);
+        let end = if reached { target } else { boundary };
+        let (lower, upper) = if up { (price, end) } else { (end, price) };
+        amount0 += tick_math::amount0_for_liquidity(lower, upper, liquidity);
+        amount1 += tick_math::amount1_for_liquidity(lower, upper, liquidity);
+        if reached {
+            break;
+        }
+        let net = next.map_or(0, |t| t.liquidity_net);
+        let net = if up { net } else { -net };
+        liquidity = (liquidity as i128).saturating_add(net).max(0) as u128;
+        price = boundary;
+    }
+
+    let (amount_in, amount_out) = if up {
+        (amount1, amount0)
+    } else {
+        (amount0, amount1)
+    };
+    let denominator = FEE_DENOMINATOR as u32;
+    let gross_in = amount_in * U256::from(denominator) / U256::from(denominator - fee);
+    Ok((gross_in, amount_out))
+}
+
+/// Protocol fee accrued by a V2 pair since the last liquidity event, as the
+/// amount of LP tokens the pair will mint to the factory's `feeTo`.
+pub fn v2_pending_protocol_fee(
+    reserve0: u128,
+    reserve1: u128,
+    k_last: U256,
+    total_supply: U256,
+) -> U256 {
+    let root_k = (U256::from(reserve0) * U256::from(reserve1)).integer_sqrt();
+    let root_k_last = k_last.integer_sqrt();
+    if k_last.is_zero() || root_k <= root_k_last {
+        return U256::zero();
+    }
+    total_supply * (root_k - root_k_last) / (root_k * 5 + root_k_last)
+}
+
+/// Averaging window of the TWAP guard when none is given, in seconds.
+pub const DEFAULT_TWAP_WINDOW: u32 = 1800;
+
+/// Refuses swaps when the spot price of a pool on the route deviates from
+/// its time-weighted average by more than `max_deviation_bps`.
+#[derive(Debug, Clone, Copy)]
+pub struct TwapGuard {
+    pub window: u32,
+    pub max_deviation_bps: u32,
+}
+
+/// Time-weighted average tick of a V3 pool over the last `window` seconds,
+/// rounded towards negative infinity like the periphery `OracleLibrary`.
+pub async fn twap_tick<M: Middleware + 'static>(
+    pool: &UNISWAP_V3_POOL<M>,
+    window: u32,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    if window == 0 {
+        return Err("The TWAP window must be at least one second".into());
+    }
+    let (tick_cumulatives, _) = pool.observe(vec![window, 0]).call().await.map_err(|e| {
+        // The pool reverts with OLD when its oldest observation is too recent
+        if e.to_string().contains("OLD") {
+            format!(
+                "The observations of pool {:?} do not cover the last {}s, raise its observation cardinality",
+                pool.address(),
+                window
+            )
+        } else {
+            e.to_string()
+        }
+    })?;
+    let delta = tick_cumulatives[1] - tick_cumulatives[0];
+    let window = window as i64;
+    let mut tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        tick -= 1;
+    }
+    Ok(tick as i32)
+}
+
+/// Raw units of `token_out` per raw unit of `token_in` at `tick`.
+pub fn tick_price(token_in: Address, token_out: Address, tick: i32) -> f64 {
+    directional_price(token_in, token_out, 1.0001f64.powi(tick))
+}
+
+/// Distance between a spot price and a reference price, in basis points of
+/// the reference.
+pub fn deviation_bps(spot_price: f64, reference_price: f64) -> f64 {
+    (spot_price / reference_price - 1.0).abs() * BPS as f64
+}
+
+/// Relative change in value of a V2 position against holding the deposited
+/// tokens, after the pair price moved by `price_ratio` (current price over
+/// the price at deposit). Always zero or negative.
+pub fn impermanent_loss_v2(price_ratio: f64) -> f64 {
+    2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0
+}
+
+/// Splits a `--path` argument such as `A:500:B:3000:C` into its token
+/// identifiers and fees.
+pub fn parse_path_spec(spec: &str) -> Result<(Vec<String>, Vec<u32>), String> {
+    let parts: Vec<&str> = spec.split(':').map(str::trim).collect();
+    if parts.len() < 3 || parts.len().is_multiple_of(2) || parts.iter().any(|p| p.is_empty()) {
+        return Err(format!(
+            "Invalid path {}, expected TOKEN:FEE:TOKEN[:FEE:TOKEN...]",
+            spec
+        ));
+    }
+    let tokens = parts.iter().step_by(2).map(|t| t.to_string()).collect();
+    let fees = parts
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .map(|fee| {
+            fee.parse::<u32>()
+                .map_err(|_| format!("Invalid fee {} in path {}", fee, spec))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((tokens, fees))
+}
+
+/// Splits a V2 `--path` argument such as `A:B:C` into its token identifiers.
+pub fn parse_v2_path_spec(spec: &str) -> Result<Vec<String>, String> {
+    let tokens: Vec<String> = spec.split(':').map(|t| t.trim().to_string()).collect();
+    if tokens.len() < 2 || tokens.iter().any(|t| t.is_empty()) {
+        return Err(format!(
+            "Invalid path {}, expected TOKEN:TOKEN[:TOKEN...]",
+            spec
+        ));
+    }
+    Ok(tokens)
+}
+
+/// Formats a fee tier as a percentage, e.g. 500 as `0.05%`.
+pub fn format_fee(fee: u32) -> String {
+    format!("{}%", fee as f64 / 10_000.0)
+}
+
+pub async fn get_pool<M: Middleware + 'static>(
+    factory: &UNISWAP_V3_FACTORY<M>,
+    token_a: Address,
+    token_b: Address,
+    fee: u32,
+) -> Result<Option<Address>, Box<dyn std::error::Error>> {
+    let pool = factory
+        .get_pool(token_a, token_b, fee)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(pool).filter(|pool| !pool.is_zero()))
+}
+
+/// Returns the fee tier, pool and in-range liquidity of the deepest pool
+/// between two tokens, or `None` if no pool exists.
+pub async fn deepest_pool<M: Middleware + 'static>(
+    factory: &UNISWAP_V3_FACTORY<M>,
+    provider: Arc<M>,
+    token_a: Address,
+    token_b: Address,
+) -> Result<Option<(u32, Address, u128)>, Box<dyn std::error::Error>> {
+    let mut best = None;
+    for fee in FEE_TIERS {
+        let Some(pool) = get_pool(factory, token_a, token_b, fee).await? else {
+            continue;
+        };
+        let liquidity = UNISWAP_V3_POOL::new(pool, Arc::clone(&provider))
+            .liquidity()
+            .call()
+            .await
+            .map_err(|e| e.to_string())?;
+        // Liquidity of pools for the same pair is expressed in the same unit,
+        // so in-range liquidity can be compared directly across fee tiers.
+        if best.is_none_or(|(_, _, best_liquidity)| liquidity > best_liquidity) {
+            best = Some((fee, pool, liquidity));
+        }
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    #[test]
+    fn encode_packs_tokens_and_fees() {
+        let path = SwapPath::new(vec![token(1), token(2), token(3)], vec![500, 3000]).unwrap();
+        let encoded = path.encode();
+        assert_eq!(encoded.len(), 20 + 2 * 23);
+        assert_eq!(&encoded[..20], token(1).as_bytes());
+        assert_eq!(&encoded[20..23], &[0x00, 0x01, 0xf4]);
+        assert_eq!(&encoded[23..43], token(2).as_bytes());
+        assert_eq!(&encoded[43..46], &[0x00, 0x0b, 0xb8]);
+        assert_eq!(&encoded[46..], token(3).as_bytes());
+    }
+
+    #[test]
+    fn encode_single_hop_with_largest_fee() {
+        let encoded = SwapPath::single(token(1), token(2), MAX_FEE).encode();
+        assert_eq!(encoded.len(), 43);
+        assert_eq!(&encoded[20..23], &[0xff, 0xff, 0xff]);
+        assert!(SwapPath::new(vec![token(1), token(2)], vec![MAX_FEE + 1]).is_err());
+    }
+
+    #[test]
+    fn encode_reversed_walks_from_the_output() {
+        let path = SwapPath::new(vec![token(1), token(2), token(3)], vec![500, 3000]).unwrap();
+        let reversed = SwapPath::new(vec![token(3), token(2), token(1)], vec![3000, 500]).unwrap();
+        assert_eq!(path.encode_reversed(), reversed.encode());
+        let encoded = path.encode_reversed();
+        assert_eq!(&encoded[..20], token(3).as_bytes());
+        assert_eq!(&encoded[20..23], &[0x00, 0x0b, 0xb8]);
+        assert_eq!(&encoded[46..], token(1).as_bytes());
+    }
+
+    #[test]
+    fn new_rejects_mismatched_fees() {
+        assert!(SwapPath::new(vec![token(1)], vec![]).is_err());
+        assert!(SwapPath::new(vec![token(1), token(2)], vec![500, 500]).is_err());
+    }
+
+    #[test]
+    fn parse_path_spec_splits_tokens_and_fees() {
+        let (tokens, fees) = parse_path_spec("WETH:500:USDC:100:DAI").unwrap();
+        assert_eq!(tokens, vec!["WETH", "USDC", "DAI"]);
+        assert_eq!(fees, vec![500, 100]);
+    }
+
+    #[test]
+    fn parse_path_spec_rejects_malformed_paths() {
+        for spec in [
+            "A:500:B:",
+            "A:500:",
+            ":500:B",
+            "A:B",
+            "A",
+            "A::B",
+            "A:fee:B",
+            "A:500:B:100",
+        ] {
+            assert!(parse_path_spec(spec).is_err(), "{} was accepted", spec);
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use crate::bridge::lifi_types::{LifiChain, LifiConnection};
 use crate::bridge::{LifiRoute, LifiToken};
-use ethers::types::{Block, H256, U256};
+use crate::lookalike::SuspiciousTransfer;
+use ethers::types::{Address, Block, H256, U256};
 use ethers::types::{Transaction, U64};
 use prettytable::{Cell, Row, Table};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn format_terminal_link(url: &str, text: &str) -> String {
     format!("\x1B]8;;{}\x1B\\{}\x1B]8;;\x1B\\", url, text)
@@ -20,6 +24,33 @@ pub fn abbreviate_address(address: &str) -> String {
     format!("{}...{}", &address[..6], &address[address.len() - 4..])
 }
 
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a number of seconds as a short countdown, e.g. "23h 05m".
+pub fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, secs % 60)
+    }
+}
+
+/// Asks a yes/no question on the terminal, defaulting to no.
+pub fn confirm(prompt: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 pub fn print_block_details(block: &Block<H256>) {
     let mut table = Table::new();
     table.add_row(Row::new(vec![
@@ -71,7 +102,19 @@ pub fn print_block_details(block: &Block<H256>) {
     table.printstd();
 }
 
-pub fn print_tx_details(tx: &Transaction) {
+/// Formats an address followed by its whitelist or ENS name, if known.
+pub fn format_named_address(address: Address, names: &HashMap<Address, String>) -> String {
+    match names.get(&address) {
+        Some(name) => format!("{:?} ({})", address, name),
+        None => format!("{:?}", address),
+    }
+}
+
+fn format_named_recipient(to: Option<Address>, names: &HashMap<Address, String>) -> String {
+    to.map_or("None".to_string(), |to| format_named_address(to, names))
+}
+
+pub fn print_tx_details(tx: &Transaction, names: &HashMap<Address, String>) {
     let mut table = Table::new();
     table.add_row(Row::new(vec![
         Cell::new("Field").style_spec("b"),
@@ -84,11 +127,11 @@ pub fn print_tx_details(tx: &Transaction) {
     ]));
     table.add_row(Row::new(vec![
         Cell::new("From"),
-        Cell::new(&format!("{:?}", tx.from)),
+        Cell::new(&format_named_address(tx.from, names)),
     ]));
     table.add_row(Row::new(vec![
         Cell::new("To"),
-        Cell::new(&format!("{:?}", tx.to)),
+        Cell::new(&format_named_recipient(tx.to, names)),
     ]));
     table.add_row(Row::new(vec![
         Cell::new("Nonce"),
@@ -263,7 +306,7 @@ pub fn print_routes(routes: &[LifiRoute]) {
     table.printstd();
 }
 
-pub fn print_txs(transactions: &[Transaction]) {
+pub fn print_txs(transactions: &[Transaction], names: &HashMap<Address, String>) {
     let mut table = Table::new();
 
     table.add_row(Row::new(vec![
@@ -280,8 +323,8 @@ pub fn print_txs(transactions: &[Transaction]) {
         table.add_row(Row::new(vec![
             Cell::new(&format!("{:?}", tx.hash)),
             Cell::new(&tx.block_number.unwrap_or(U64::zero()).to_string()),
-            Cell::new(&format!("{:?}", tx.from)),
-            Cell::new(&format!("{:?}", tx.to)),
+            Cell::new(&format_named_address(tx.from, names)),
+            Cell::new(&format_named_recipient(tx.to, names)),
             Cell::new(&tx.value.to_string()),
             Cell::new(&tx.gas.to_string()),
             Cell::new(&tx.gas_price.unwrap_or(U256::zero()).to_string()),
@@ -290,3 +333,36 @@ pub fn print_txs(transactions: &[Transaction]) {
 
     table.printstd();
 }
+
+pub fn print_suspicious_transfers(
+    transfers: &[SuspiciousTransfer],
+    names: &HashMap<Address, String>,
+) {
+    let mut table = Table::new();
+
+    table.add_row(Row::new(vec![
+        Cell::new("Hash").style_spec("b"),
+        Cell::new("Token").style_spec("b"),
+        Cell::new("Lookalike").style_spec("b"),
+        Cell::new("Mimics").style_spec("b"),
+        Cell::new("Matching").style_spec("b"),
+    ]));
+
+    for transfer in transfers {
+        table.add_row(Row::new(vec![
+            Cell::new(&format!("{:?}", transfer.tx_hash)),
+            Cell::new(&transfer.token_symbol),
+            Cell::new(&format_named_address(transfer.counterparty, names)),
+            Cell::new(&format!(
+                "{:?} ({})",
+                transfer.mimics, transfer.mimics_label
+            )),
+            Cell::new(&format!(
+                "first {}, last {}",
+                transfer.similarity.prefix, transfer.similarity.suffix
+            )),
+        ]));
+    }
+
+    table.printstd();
+}
@@ -1,17 +1,151 @@
 use ethers::core::k256::ecdsa::SigningKey;
 use ethers::prelude::*;
-use ethers::types::Address;
+use ethers::types::{Address, Signature};
+use ethers::utils::hash_message;
 use openzeppelin_rs::ERC20;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::policy::SpendingPolicy;
+use crate::utils;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WalletInfo {
     pub address: String,
     pub name: Option<String>,
+    /// Chain the wallet is whitelisted on, `None` meaning all chains.
+    pub chain_id: Option<u64>,
+    /// Unix timestamp from which the entry can be used.
+    #[serde(default)]
+    pub active_from: u64,
+}
+
+impl WalletInfo {
+    pub fn applies_to(&self, chain_id: u64) -> bool {
+        self.chain_id.is_none_or(|id| id == chain_id)
+    }
+
+    fn key(&self) -> String {
+        match Address::from_str(&self.address) {
+            Ok(address) => wallet_key(address, self.chain_id),
+            Err(_) => self.address.clone(),
+        }
+    }
+}
+
+/// How a token behaves on transfer. Proxies are detected from the EIP-1967
+/// storage slots, the other non-standard types have to be declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenType {
+    #[default]
+    Standard,
+    FeeOnTransfer,
+    Rebasing,
+    Proxy,
+}
+
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TokenType::Standard => "standard",
+            TokenType::FeeOnTransfer => "fee-on-transfer",
+            TokenType::Rebasing => "rebasing",
+            TokenType::Proxy => "proxy",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+/// `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`
+const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+/// Token metadata read from the chain when a token is whitelisted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMetadata {
+    pub symbol: String,
+    pub decimals: Option<u8>,
+    pub token_name: Option<String>,
+    pub token_type: TokenType,
+    pub code_hash: Option<String>,
+    pub implementation: Option<String>,
+}
+
+impl TokenMetadata {
+    /// Metadata for a token known only by its symbol, e.g. from an import
+    /// that was not checked on-chain.
+    pub fn from_symbol(symbol: String) -> Self {
+        TokenMetadata {
+            symbol,
+            decimals: None,
+            token_name: None,
+            token_type: TokenType::Standard,
+            code_hash: None,
+            implementation: None,
+        }
+    }
+
+    /// Reads symbol, decimals, name and bytecode of a token. The token type is
+    /// `declared` if given, otherwise `Proxy` when an EIP-1967 slot is set.
+    pub async fn fetch<M: Middleware + 'static>(
+        address: Address,
+        declared: Option<TokenType>,
+        provider: Arc<M>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let code = provider
+            .get_code(address, None)
+            .await
+            .map_err(|e| e.to_string())?;
+        if code.is_empty() {
+            return Err(format!("{:?} has no contract code", address).into());
+        }
+        let implementation = read_eip1967_target(&provider, address).await?;
+
+        let token = ERC20::new(address, provider);
+        let symbol = token.symbol().call().await?;
+        let decimals = token.decimals().call().await?;
+        let token_name = token.name().call().await.ok();
+
+        let token_type = match (declared, implementation) {
+            (Some(token_type), _) => token_type,
+            (None, Some(_)) => TokenType::Proxy,
+            (None, None) => TokenType::Standard,
+        };
+        Ok(TokenMetadata {
+            symbol,
+            decimals: Some(decimals),
+            token_name,
+            token_type,
+            code_hash: Some(format!("{:?}", H256::from(ethers::utils::keccak256(&code)))),
+            implementation: implementation.map(|address| format!("{:?}", address)),
+        })
+    }
+}
+
+/// Returns the implementation (or beacon) address stored in the EIP-1967
+/// proxy slots of `address`, if any.
+async fn read_eip1967_target<M: Middleware>(
+    provider: &Arc<M>,
+    address: Address,
+) -> Result<Option<Address>, Box<dyn std::error::Error>> {
+    for slot in [EIP1967_IMPLEMENTATION_SLOT, EIP1967_BEACON_SLOT] {
+        let value = provider
+            .get_storage_at(address, H256::from_str(slot)?, None)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !value.is_zero() {
+            return Ok(Some(Address::from(value)));
+        }
+    }
+    Ok(None)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,12 +154,87 @@ pub struct TokenInfo {
     pub chain_id: u64,
     pub symbol: String,
     pub name: Option<String>,
+    /// Unix timestamp from which the entry can be used.
+    #[serde(default)]
+    pub active_from: u64,
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    /// Name returned by the token's `name()`, as opposed to the local label.
+    #[serde(default)]
+    pub token_name: Option<String>,
+    #[serde(default)]
+    pub token_type: TokenType,
+    /// keccak256 of the deployed bytecode when the token was added.
+    #[serde(default)]
+    pub code_hash: Option<String>,
+    /// EIP-1967 implementation or beacon address for proxy tokens.
+    #[serde(default)]
+    pub implementation: Option<String>,
+}
+
+impl TokenInfo {
+    pub fn metadata(&self) -> TokenMetadata {
+        TokenMetadata {
+            symbol: self.symbol.clone(),
+            decimals: self.decimals,
+            token_name: self.token_name.clone(),
+            token_type: self.token_type,
+            code_hash: self.code_hash.clone(),
+            implementation: self.implementation.clone(),
+        }
+    }
+
+    fn key(&self) -> String {
+        match Address::from_str(&self.address) {
+            Ok(address) => token_key(address, self.chain_id),
+            Err(_) => format!("{}:{}", self.address, self.chain_id),
+        }
+    }
+}
+
+fn wallet_key(address: Address, chain_id: Option<u64>) -> String {
+    match chain_id {
+        Some(chain_id) => format!("{:?}:{}", address, chain_id),
+        None => format!("{:?}", address),
+    }
+}
+
+fn token_key(address: Address, chain_id: u64) -> String {
+    format!("{:?}:{}", address, chain_id)
+}
+
+/// A reduction of the activation delay, which only applies once the delay
+/// it replaces has elapsed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DelayChange {
+    pub delay_secs: u64,
+    pub effective_from: u64,
+}
+
+/// Detached admin signature of the whitelist or addressbook file, stored
+/// next to it.
+#[derive(Debug, Serialize, Deserialize)]
+struct WhitelistSignature {
+    signer: String,
+    signature: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Whitelist {
     wallet_addresses: HashMap<String, WalletInfo>,
     token_addresses: HashMap<String, TokenInfo>,
+    #[serde(default)]
+    spending_policies: Vec<SpendingPolicy>,
+    /// Cooling-off period applied to newly added wallets and tokens.
+    #[serde(default)]
+    activation_delay_secs: u64,
+    #[serde(default)]
+    activation_delay_change: Option<DelayChange>,
+    /// Set when the file on disk could not be verified against the admin
+    /// signature. A read-only whitelist can be displayed but not used to
+    /// authorize transactions or be saved.
+    #[serde(skip)]
+    read_only: Option<String>,
 }
 
 impl Whitelist {
@@ -33,66 +242,265 @@ impl Whitelist {
         Whitelist {
             wallet_addresses: HashMap::new(),
             token_addresses: HashMap::new(),
+            spending_policies: Vec::new(),
+            activation_delay_secs: 0,
+            activation_delay_change: None,
+            read_only: None,
         }
     }
 
-    pub fn add_wallet_address(&mut self, address: String, name: Option<String>) {
-        self.wallet_addresses
-            .insert(address.clone(), WalletInfo { address, name });
+    pub fn add_wallet_address(
+        &mut self,
+        address: Address,
+        name: Option<String>,
+        chain_id: Option<u64>,
+    ) {
+        let key = wallet_key(address, chain_id);
+        let active_from = self
+            .wallet_addresses
+            .get(&key)
+            .map_or_else(|| self.next_activation_time(), |info| info.active_from);
+        self.wallet_addresses.insert(
+            key,
+            WalletInfo {
+                address: format!("{:?}", address),
+                name,
+                chain_id,
+                active_from,
+            },
+        );
     }
 
-    pub fn remove_wallet_address(&mut self, address: &str) {
-        self.wallet_addresses.remove(address);
+    pub fn remove_wallet_address(&mut self, address: Address, chain_id: Option<u64>) {
+        self.wallet_addresses.remove(&wallet_key(address, chain_id));
     }
 
     pub async fn add_token_address(
         &mut self,
-        address: String,
+        address: Address,
         chain_id: u64,
         name: Option<String>,
+        token_type: Option<TokenType>,
         provider: Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let address = Address::from_str(&address)?;
-        let token = ERC20::new(address, provider);
-        let symbol = token.symbol().call().await?;
+        let metadata = TokenMetadata::fetch(address, token_type, provider).await?;
+        self.insert_token(address, chain_id, name, metadata);
+        Ok(())
+    }
+
+    /// Adds a token whose metadata has already been read from the chain.
+    pub fn insert_token(
+        &mut self,
+        address: Address,
+        chain_id: u64,
+        name: Option<String>,
+        metadata: TokenMetadata,
+    ) {
+        let key = token_key(address, chain_id);
+        let active_from = self
+            .token_addresses
+            .get(&key)
+            .map_or_else(|| self.next_activation_time(), |info| info.active_from);
         self.token_addresses.insert(
-            format!("{}:{}", address, chain_id),
+            key,
             TokenInfo {
-                address: address.to_string(),
+                address: format!("{:?}", address),
                 chain_id,
-                symbol,
+                symbol: metadata.symbol,
                 name,
+                active_from,
+                decimals: metadata.decimals,
+                token_name: metadata.token_name,
+                token_type: metadata.token_type,
+                code_hash: metadata.code_hash,
+                implementation: metadata.implementation,
             },
         );
-        Ok(())
     }
 
-    pub fn remove_token_address(&mut self, address: &str, chain_id: u64) {
-        self.token_addresses
-            .remove(&format!("{}:{}", address, chain_id));
+    /// Replaces the cached metadata of a token, keeping its label and
+    /// activation time.
+    pub fn update_token_metadata(
+        &mut self,
+        address: Address,
+        chain_id: u64,
+        metadata: TokenMetadata,
+    ) {
+        if let Some(info) = self.token_addresses.get_mut(&token_key(address, chain_id)) {
+            info.symbol = metadata.symbol;
+            info.decimals = metadata.decimals;
+            info.token_name = metadata.token_name;
+            info.token_type = metadata.token_type;
+            info.code_hash = metadata.code_hash;
+            info.implementation = metadata.implementation;
+        }
+    }
+
+    pub fn token_decimals(&self, address: Address, chain_id: u64) -> Option<u8> {
+        self.get_token(address, chain_id)
+            .and_then(|info| info.decimals)
+    }
+
+    /// Formats an amount of `token` (native token if `None`) using the cached
+    /// decimals and symbol, falling back to raw units for unknown tokens.
+    pub fn format_amount(&self, token: Option<Address>, chain_id: u64, amount: U256) -> String {
+        let (decimals, symbol) = match token {
+            None => (Some(18), "ETH".to_string()),
+            Some(address) => match self.get_token(address, chain_id) {
+                Some(info) => (info.decimals, info.symbol.clone()),
+                None => (None, format!("{:?}", address)),
+            },
+        };
+        match decimals.and_then(|d| ethers::utils::format_units(amount, d as u32).ok()) {
+            Some(formatted) => format!("{} {}", formatted, symbol),
+            None => format!("{} units of {}", amount, symbol),
+        }
+    }
+
+    pub fn get_wallet(&self, address: Address, chain_id: Option<u64>) -> Option<&WalletInfo> {
+        self.wallet_addresses.get(&wallet_key(address, chain_id))
+    }
+
+    pub fn get_token(&self, address: Address, chain_id: u64) -> Option<&TokenInfo> {
+        self.token_addresses.get(&token_key(address, chain_id))
+    }
+
+    pub fn remove_token_address(&mut self, address: Address, chain_id: u64) {
+        self.token_addresses.remove(&token_key(address, chain_id));
+    }
+
+    /// Returns true if the wallet is whitelisted on `chain_id`, either through
+    /// an entry scoped to that chain or through an all-chains entry, and that
+    /// entry is past its cooling-off period.
+    pub fn is_wallet_whitelisted(&self, address: Address, chain_id: u64) -> bool {
+        self.wallet_active_from(address, chain_id)
+            .is_some_and(|active_from| active_from <= utils::unix_now())
+    }
+
+    pub fn is_token_whitelisted(&self, address: Address, chain_id: u64) -> bool {
+        self.token_active_from(address, chain_id)
+            .is_some_and(|active_from| active_from <= utils::unix_now())
     }
 
-    pub fn is_wallet_whitelisted(&self, address: &str) -> bool {
-        self.wallet_addresses.contains_key(address)
+    /// Returns when the wallet becomes usable on `chain_id`, or `None` if it
+    /// is not in the whitelist at all.
+    pub fn wallet_active_from(&self, address: Address, chain_id: u64) -> Option<u64> {
+        [
+            wallet_key(address, Some(chain_id)),
+            wallet_key(address, None),
+        ]
+        .iter()
+        .filter_map(|key| self.wallet_addresses.get(key))
+        .map(|info| info.active_from)
+        .min()
     }
 
-    pub fn is_token_whitelisted(&self, address: &str, chain_id: u64) -> bool {
+    pub fn token_active_from(&self, address: Address, chain_id: u64) -> Option<u64> {
         self.token_addresses
-            .contains_key(&format!("{}:{}", address, chain_id))
+            .get(&token_key(address, chain_id))
+            .map(|info| info.active_from)
+    }
+
+    /// The cooling-off period currently applied to new entries.
+    pub fn activation_delay(&self) -> u64 {
+        match &self.activation_delay_change {
+            Some(change) if change.effective_from <= utils::unix_now() => change.delay_secs,
+            _ => self.activation_delay_secs,
+        }
     }
 
+    pub fn pending_activation_delay(&self) -> Option<&DelayChange> {
+        self.activation_delay_change
+            .as_ref()
+            .filter(|change| change.effective_from > utils::unix_now())
+    }
+
+    /// Changes the cooling-off period. Increases apply immediately, while a
+    /// reduction only applies once the current delay has elapsed, so that the
+    /// delay cannot be lowered to rush in a new entry.
+    pub fn set_activation_delay(&mut self, delay_secs: u64) {
+        let current = self.activation_delay();
+        self.activation_delay_secs = current;
+        if delay_secs >= current {
+            self.activation_delay_secs = delay_secs;
+            self.activation_delay_change = None;
+        } else {
+            self.activation_delay_change = Some(DelayChange {
+                delay_secs,
+                effective_from: utils::unix_now() + current,
+            });
+        }
+    }
+
+    fn next_activation_time(&self) -> u64 {
+        utils::unix_now() + self.activation_delay()
+    }
+
+    /// Adds a spending policy, replacing any existing policy with the same
+    /// chain, token and wallet scope.
+    pub fn set_spending_policy(&mut self, policy: SpendingPolicy) {
+        self.spending_policies.retain(|existing| {
+            existing.chain_id != policy.chain_id
+                || existing.token != policy.token
+                || existing.wallet != policy.wallet
+        });
+        self.spending_policies.push(policy);
+    }
+
+    pub fn remove_spending_policy(
+        &mut self,
+        chain_id: u64,
+        token: Option<Address>,
+        wallet: Option<Address>,
+    ) {
+        self.spending_policies
+            .retain(|policy| !policy.targets(chain_id, token, wallet));
+    }
+
+    pub fn get_spending_policies(&self) -> &[SpendingPolicy] {
+        &self.spending_policies
+    }
+
+    /// Writes the whitelist and signs the written bytes with the admin key.
     pub fn save(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(reason) = &self.read_only {
+            return Err(format!("Whitelist is read-only: {}", reason).into());
+        }
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(file_path, json)?;
-        Ok(())
+        fs::write(file_path, &json)?;
+        sign_file(file_path, &json)
     }
 
+    /// Loads the whitelist, marking it read-only if its signature is missing
+    /// or was not produced by the admin key.
     pub fn load(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let file_content = fs::read_to_string(file_path)?;
-        let whitelist: Whitelist = serde_json::from_str(&file_content)?;
+        let mut whitelist: Whitelist = serde_json::from_str(&file_content)?;
+        whitelist.read_only = verify_signature(file_path, &file_content).err();
+        // Re-key entries so that files written with checksummed or otherwise
+        // differently formatted addresses are looked up consistently.
+        whitelist.wallet_addresses = whitelist
+            .wallet_addresses
+            .into_values()
+            .map(|info| (info.key(), info))
+            .collect();
+        whitelist.token_addresses = whitelist
+            .token_addresses
+            .into_values()
+            .map(|info| (info.key(), info))
+            .collect();
         Ok(whitelist)
     }
 
+    pub fn read_only_reason(&self) -> Option<&str> {
+        self.read_only.as_deref()
+    }
+
+    /// Accepts the current content as reviewed so that it can be re-signed.
+    pub fn clear_read_only(&mut self) {
+        self.read_only = None;
+    }
+
     pub fn get_wallet_addresses(&self) -> &HashMap<String, WalletInfo> {
         &self.wallet_addresses
     }
@@ -101,11 +509,103 @@ impl Whitelist {
         &self.token_addresses
     }
 
-    pub fn get_wallet_by_name(&self, name: &str) -> Option<&WalletInfo> {
-        self.wallet_addresses
+    /// Tokens whitelisted on `chain_id` whose symbol matches, ignoring case.
+    pub fn get_tokens_by_symbol(&self, symbol: &str, chain_id: u64) -> Vec<&TokenInfo> {
+        let mut tokens: Vec<&TokenInfo> = self
+            .token_addresses
             .values()
-            .find(|info| info.name.as_deref() == Some(name))
+            .filter(|info| info.chain_id == chain_id && info.symbol.eq_ignore_ascii_case(symbol))
+            .collect();
+        tokens.sort_by(|a, b| a.address.cmp(&b.address));
+        tokens
+    }
+
+    /// Name of a wallet whitelisted on `chain_id`, preferring a chain-scoped
+    /// entry over an all-chains entry.
+    pub fn wallet_name(&self, address: Address, chain_id: u64) -> Option<&str> {
+        self.get_wallet(address, Some(chain_id))
+            .or_else(|| self.get_wallet(address, None))
+            .and_then(|info| info.name.as_deref())
+    }
+
+    /// Looks up a wallet by name, preferring an entry scoped to `chain_id`
+    /// over an all-chains entry with the same name.
+    pub fn get_wallet_by_name(&self, name: &str, chain_id: u64) -> Option<&WalletInfo> {
+        let mut matches = self
+            .wallet_addresses
+            .values()
+            .filter(|info| info.name.as_deref() == Some(name) && info.applies_to(chain_id));
+        let first = matches.next()?;
+        if first.chain_id.is_some() {
+            return Some(first);
+        }
+        Some(
+            matches
+                .find(|info| info.chain_id.is_some())
+                .unwrap_or(first),
+        )
+    }
+}
+
+fn signature_path(file_path: &str) -> String {
+    format!("{}.sig", file_path)
+}
+
+fn admin_wallet() -> Result<LocalWallet, Box<dyn std::error::Error>> {
+    let key = env::var("WHITELIST_ADMIN_PRIVATE_KEY").map_err(|_| {
+        "WHITELIST_ADMIN_PRIVATE_KEY is not set, cannot sign the whitelist or addressbook"
+    })?;
+    Ok(key.parse::<LocalWallet>()?)
+}
+
+/// The address whitelist signatures must recover to. It is pinned through
+/// `WHITELIST_ADMIN_ADDRESS`, falling back to the address of the admin key.
+fn trusted_admin_address() -> Result<Address, String> {
+    if let Ok(address) = env::var("WHITELIST_ADMIN_ADDRESS") {
+        return Address::from_str(&address)
+            .map_err(|e| format!("invalid WHITELIST_ADMIN_ADDRESS: {}", e));
+    }
+    admin_wallet().map(|wallet| wallet.address()).map_err(|_| {
+        "neither WHITELIST_ADMIN_ADDRESS nor WHITELIST_ADMIN_PRIVATE_KEY is set".to_string()
+    })
+}
+
+/// Signs `content`, the bytes of the file at `file_path`, with the admin key
+/// and writes the signature next to the file.
+pub fn sign_file(file_path: &str, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let admin = admin_wallet()?;
+    let signature = admin.sign_hash(hash_message(content.as_bytes()))?;
+    let signature = WhitelistSignature {
+        signer: format!("{:?}", admin.address()),
+        signature: format!("0x{}", signature),
+    };
+    fs::write(
+        signature_path(file_path),
+        serde_json::to_string_pretty(&signature)?,
+    )?;
+    Ok(())
+}
+
+/// Checks that `content`, the bytes of the file at `file_path`, carries a
+/// signature by the admin key.
+pub fn verify_signature(file_path: &str, content: &str) -> Result<(), String> {
+    let admin = trusted_admin_address()?;
+    let signature_file = fs::read_to_string(signature_path(file_path))
+        .map_err(|_| format!("signature file {} is missing", signature_path(file_path)))?;
+    let stored: WhitelistSignature = serde_json::from_str(&signature_file)
+        .map_err(|e| format!("signature file is malformed: {}", e))?;
+    let signature = Signature::from_str(&stored.signature)
+        .map_err(|e| format!("signature is malformed: {}", e))?;
+    let signer = signature
+        .recover(content.as_bytes())
+        .map_err(|e| format!("signature does not verify: {}", e))?;
+    if signer != admin {
+        return Err(format!(
+            "file was modified outside the tool or signed by another key (expected admin {:?})",
+            admin
+        ));
     }
+    Ok(())
 }
 
 impl Default for Whitelist {
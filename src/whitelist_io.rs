@@ -0,0 +1,654 @@
+use ethers::types::{Address, Chain};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::config::{get_chain_config, is_chain_supported};
+use crate::whitelist::{TokenMetadata, TokenType, Whitelist};
+
+const CSV_HEADER: &str = "type,address,chain_id,name,symbol";
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Picks the format from a file extension, defaulting to JSON.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            ExportFormat::Csv
+        } else {
+            ExportFormat::Json
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedWallet {
+    pub address: String,
+    pub name: Option<String>,
+    pub chain_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedToken {
+    pub address: String,
+    pub chain_id: u64,
+    pub symbol: String,
+    pub name: Option<String>,
+}
+
+/// Portable form of the whitelist entries, meant to be reviewed and shared.
+/// Spending policies and activation times are local and are not exported.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WhitelistExport {
+    pub wallets: Vec<ExportedWallet>,
+    pub tokens: Vec<ExportedToken>,
+}
+
+impl WhitelistExport {
+    /// Builds an export sorted by chain and address so that diffs stay stable.
+    pub fn from_whitelist(whitelist: &Whitelist) -> Self {
+        let mut wallets: Vec<ExportedWallet> = whitelist
+            .get_wallet_addresses()
+            .values()
+            .map(|info| ExportedWallet {
+                address: info.address.clone(),
+                name: info.name.clone(),
+                chain_id: info.chain_id,
+            })
+            .collect();
+        wallets.sort_by(|a, b| (a.chain_id, &a.address).cmp(&(b.chain_id, &b.address)));
+
+        let mut tokens: Vec<ExportedToken> = whitelist
+            .get_token_addresses()
+            .values()
+            .map(|info| ExportedToken {
+                address: info.address.clone(),
+                chain_id: info.chain_id,
+                symbol: info.symbol.clone(),
+                name: info.name.clone(),
+            })
+            .collect();
+        tokens.sort_by(|a, b| (a.chain_id, &a.address).cmp(&(b.chain_id, &b.address)));
+
+        WhitelistExport { wallets, tokens }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String, Box<dyn std::error::Error>> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+            ExportFormat::Csv => {
+                let mut lines = vec![CSV_HEADER.to_string()];
+                for wallet in &self.wallets {
+                    let chain = wallet
+                        .chain_id
+                        .map_or("all".to_string(), |id| id.to_string());
+                    lines.push(csv_line(&[
+                        "wallet",
+                        &wallet.address,
+                        &chain,
+                        wallet.name.as_deref().unwrap_or(""),
+                        "",
+                    ]));
+                }
+                for token in &self.tokens {
+                    lines.push(csv_line(&[
+                        "token",
+                        &token.address,
+                        &token.chain_id.to_string(),
+                        token.name.as_deref().unwrap_or(""),
+                        &token.symbol,
+                    ]));
+                }
+                Ok(lines.join("\n") + "\n")
+            }
+        }
+    }
+
+    pub fn parse(content: &str, format: ExportFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::from_str(content)?),
+            ExportFormat::Csv => {
+                let mut export = WhitelistExport::default();
+                for (index, line) in content.lines().enumerate() {
+                    let line_number = index + 1;
+                    if line.trim().is_empty() || (index == 0 && line.trim() == CSV_HEADER) {
+                        continue;
+                    }
+                    let fields =
+                        split_csv_line(line).map_err(|e| format!("line {}: {}", line_number, e))?;
+                    if fields.len() != 5 {
+                        return Err(format!(
+                            "line {}: expected 5 fields ({}), found {}",
+                            line_number,
+                            CSV_HEADER,
+                            fields.len()
+                        )
+                        .into());
+                    }
+                    let name = Some(fields[3].clone()).filter(|name| !name.is_empty());
+                    match fields[0].as_str() {
+                        "wallet" => {
+                            let chain_id = match fields[2].as_str() {
+                                "" | "all" => None,
+                                id => Some(id.parse::<u64>().map_err(|_| {
+                                    format!("line {}: invalid chain id {}", line_number, id)
+                                })?),
+                            };
+                            export.wallets.push(ExportedWallet {
+                                address: fields[1].clone(),
+                                name,
+                                chain_id,
+                            });
+                        }
+                        "token" => {
+                            let chain_id = fields[2].parse::<u64>().map_err(|_| {
+                                format!("line {}: invalid chain id {}", line_number, fields[2])
+                            })?;
+                            export.tokens.push(ExportedToken {
+                                address: fields[1].clone(),
+                                chain_id,
+                                symbol: fields[4].clone(),
+                                name,
+                            });
+                        }
+                        other => {
+                            return Err(format!(
+                                "line {}: unknown entry type {}",
+                                line_number, other
+                            )
+                            .into())
+                        }
+                    }
+                }
+                Ok(export)
+            }
+        }
+    }
+}
+
+fn csv_line(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect())
+}
+
+pub enum Change {
+    AddWallet(Address, ExportedWallet),
+    RenameWallet(Address, ExportedWallet, Option<String>),
+    AddToken(Address, ExportedToken, TokenMetadata),
+    RenameToken(Address, ExportedToken, Option<String>),
+}
+
+/// Result of merging an import into the current whitelist: the changes that
+/// can be applied, and the entries that were left out because they conflict.
+#[derive(Default)]
+pub struct ImportPlan {
+    pub changes: Vec<Change>,
+    pub conflicts: Vec<String>,
+}
+
+fn scopes_overlap(a: Option<u64>, b: Option<u64>) -> bool {
+    a.is_none() || b.is_none() || a == b
+}
+
+fn describe_scope(chain_id: Option<u64>) -> String {
+    chain_id.map_or("all chains".to_string(), |id| format!("chain {}", id))
+}
+
+/// Reads the metadata of tokens given as `(address, chain_id, declared type)`
+/// on the chains that have an RPC configured. Tokens on other chains are left
+/// out of the result.
+pub async fn fetch_token_metadata(
+    tokens: &[(Address, u64, Option<TokenType>)],
+) -> HashMap<(Address, u64), Result<TokenMetadata, String>> {
+    let mut providers = HashMap::new();
+    let mut metadata = HashMap::new();
+    for &(address, chain_id, declared) in tokens {
+        let chain = match Chain::try_from(chain_id) {
+            Ok(chain) if is_chain_supported(chain) => chain,
+            _ => {
+                println!(
+                    "Note: no RPC configured for chain {}, {:?} not checked",
+                    chain_id, address
+                );
+                continue;
+            }
+        };
+        let provider = match providers.get(&chain_id) {
+            Some(provider) => Arc::clone(provider),
+            None => {
+                let provider = get_chain_config(chain).await.http;
+                providers.insert(chain_id, Arc::clone(&provider));
+                provider
+            }
+        };
+        let result = TokenMetadata::fetch(address, declared, provider)
+            .await
+            .map_err(|e| e.to_string());
+        metadata.insert((address, chain_id), result);
+    }
+    metadata
+}
+
+/// Reads the on-chain metadata of every imported token.
+pub async fn fetch_import_metadata(
+    tokens: &[ExportedToken],
+) -> HashMap<(Address, u64), Result<TokenMetadata, String>> {
+    let tokens: Vec<_> = tokens
+        .iter()
+        .filter_map(|token| {
+            Address::from_str(&token.address)
+                .ok()
+                .map(|address| (address, token.chain_id, None))
+        })
+        .collect();
+    fetch_token_metadata(&tokens).await
+}
+
+/// Merges `import` into the whitelist without modifying it. Entries already
+/// present are kept, names are updated, and conflicting entries are reported
+/// and skipped.
+pub fn plan_import(
+    whitelist: &Whitelist,
+    import: &WhitelistExport,
+    onchain_metadata: &HashMap<(Address, u64), Result<TokenMetadata, String>>,
+) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+    let mut imported_names: Vec<(String, Address, Option<u64>)> = Vec::new();
+
+    for wallet in &import.wallets {
+        let Ok(address) = Address::from_str(&wallet.address) else {
+            plan.conflicts
+                .push(format!("wallet {}: invalid address", wallet.address));
+            continue;
+        };
+
+        if let Some(name) = &wallet.name {
+            let existing = whitelist.get_wallet_addresses().values().find(|info| {
+                info.name.as_ref() == Some(name)
+                    && scopes_overlap(info.chain_id, wallet.chain_id)
+                    && Address::from_str(&info.address).is_ok_and(|a| a != address)
+            });
+            if let Some(existing) = existing {
+                plan.conflicts.push(format!(
+                    "wallet name \"{}\" maps to {} on {} in the whitelist but to {:?} on {} in the import",
+                    name,
+                    existing.address,
+                    describe_scope(existing.chain_id),
+                    address,
+                    describe_scope(wallet.chain_id)
+                ));
+                continue;
+            }
+            let duplicate = imported_names
+                .iter()
+                .find(|(other, other_address, chain_id)| {
+                    other == name
+                        && *other_address != address
+                        && scopes_overlap(*chain_id, wallet.chain_id)
+                });
+            if let Some((_, other_address, _)) = duplicate {
+                plan.conflicts.push(format!(
+                    "wallet name \"{}\" maps to both {:?} and {:?} in the import",
+                    name, other_address, address
+                ));
+                continue;
+            }
+            imported_names.push((name.clone(), address, wallet.chain_id));
+        }
+
+        match whitelist.get_wallet(address, wallet.chain_id) {
+            None => plan
+                .changes
+                .push(Change::AddWallet(address, wallet.clone())),
+            Some(existing) if existing.name != wallet.name => plan.changes.push(
+                Change::RenameWallet(address, wallet.clone(), existing.name.clone()),
+            ),
+            Some(_) => {}
+        }
+    }
+
+    for token in &import.tokens {
+        let Ok(address) = Address::from_str(&token.address) else {
+            plan.conflicts.push(format!(
+                "token {} on chain {}: invalid address",
+                token.address, token.chain_id
+            ));
+            continue;
+        };
+
+        let metadata = match onchain_metadata.get(&(address, token.chain_id)) {
+            Some(Ok(metadata)) if metadata.symbol != token.symbol => {
+                plan.conflicts.push(format!(
+                    "token {:?} on chain {}: import says {} but symbol() returns {}",
+                    address, token.chain_id, token.symbol, metadata.symbol
+                ));
+                continue;
+            }
+            Some(Err(error)) => {
+                plan.conflicts.push(format!(
+                    "token {:?} on chain {}: could not read metadata: {}",
+                    address, token.chain_id, error
+                ));
+                continue;
+            }
+            Some(Ok(metadata)) => metadata.clone(),
+            None => TokenMetadata::from_symbol(token.symbol.clone()),
+        };
+
+        let token = token.clone();
+        match whitelist.get_token(address, token.chain_id) {
+            None => plan
+                .changes
+                .push(Change::AddToken(address, token, metadata)),
+            Some(existing) if existing.symbol != token.symbol => {
+                plan.conflicts.push(format!(
+                    "token {:?} on chain {}: whitelisted as {} but imported as {}",
+                    address, token.chain_id, existing.symbol, token.symbol
+                ));
+            }
+            Some(existing) if existing.name != token.name => {
+                let previous = existing.name.clone();
+                plan.changes
+                    .push(Change::RenameToken(address, token, previous));
+            }
+            Some(_) => {}
+        }
+    }
+
+    plan
+}
+
+impl ImportPlan {
+    pub fn print(&self) {
+        if self.changes.is_empty() {
+            println!("No changes to import.");
+        }
+        for change in &self.changes {
+            match change {
+                Change::AddWallet(address, wallet) => println!(
+                    "+ wallet {:?} ({}) on {}",
+                    address,
+                    wallet.name.as_deref().unwrap_or(""),
+                    describe_scope(wallet.chain_id)
+                ),
+                Change::RenameWallet(address, wallet, previous) => println!(
+                    "~ wallet {:?} on {}: name {} -> {}",
+                    address,
+                    describe_scope(wallet.chain_id),
+                    previous.as_deref().unwrap_or("-"),
+                    wallet.name.as_deref().unwrap_or("-")
+                ),
+                Change::AddToken(address, token, _) => println!(
+                    "+ token {} {:?} ({}) on chain {}",
+                    token.symbol,
+                    address,
+                    token.name.as_deref().unwrap_or(""),
+                    token.chain_id
+                ),
+                Change::RenameToken(address, token, previous) => println!(
+                    "~ token {} {:?} on chain {}: name {} -> {}",
+                    token.symbol,
+                    address,
+                    token.chain_id,
+                    previous.as_deref().unwrap_or("-"),
+                    token.name.as_deref().unwrap_or("-")
+                ),
+            }
+        }
+        for conflict in &self.conflicts {
+            println!("! conflict, skipped: {}", conflict);
+        }
+    }
+
+    pub fn apply(self, whitelist: &mut Whitelist) {
+        for change in self.changes {
+            match change {
+                Change::AddWallet(address, wallet) | Change::RenameWallet(address, wallet, _) => {
+                    whitelist.add_wallet_address(address, wallet.name, wallet.chain_id)
+                }
+                Change::AddToken(address, token, metadata) => {
+                    whitelist.insert_token(address, token.chain_id, token.name, metadata)
+                }
+                Change::RenameToken(address, token, _) => {
+                    let metadata = whitelist
+                        .get_token(address, token.chain_id)
+                        .map(|info| info.metadata());
+                    if let Some(metadata) = metadata {
+                        whitelist.insert_token(address, token.chain_id, token.name, metadata)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A whitelisted token whose on-chain state differs from what was recorded
+/// when it was added.
+pub struct TokenDrift {
+    pub address: Address,
+    pub chain_id: u64,
+    pub symbol: String,
+    pub differences: Vec<String>,
+    pub current: Option<TokenMetadata>,
+}
+
+/// Re-reads every whitelisted token (optionally only on `chain_id`) and
+/// reports the ones whose bytecode, proxy implementation or metadata changed.
+pub async fn check_token_drift(whitelist: &Whitelist, chain_id: Option<u64>) -> Vec<TokenDrift> {
+    let mut tokens: Vec<_> = whitelist
+        .get_token_addresses()
+        .values()
+        .filter(|info| chain_id.is_none_or(|id| id == info.chain_id))
+        .filter_map(|info| {
+            // Declared types cannot be detected, so they are carried over.
+            let declared = match info.token_type {
+                TokenType::FeeOnTransfer | TokenType::Rebasing => Some(info.token_type),
+                TokenType::Standard | TokenType::Proxy => None,
+            };
+            Address::from_str(&info.address)
+                .ok()
+                .map(|address| (address, info.chain_id, declared))
+        })
+        .collect();
+    tokens.sort_by_key(|&(address, chain_id, _)| (chain_id, address));
+
+    let fetched = fetch_token_metadata(&tokens).await;
+    let mut drifts = Vec::new();
+    for (address, chain_id, _) in tokens {
+        let Some(result) = fetched.get(&(address, chain_id)) else {
+            continue;
+        };
+        let Some(info) = whitelist.get_token(address, chain_id) else {
+            continue;
+        };
+        let recorded = info.metadata();
+        let (differences, current) = match result {
+            Ok(current) => (compare_metadata(&recorded, current), Some(current.clone())),
+            Err(error) => (vec![format!("could not read token: {}", error)], None),
+        };
+        if !differences.is_empty() {
+            drifts.push(TokenDrift {
+                address,
+                chain_id,
+                symbol: recorded.symbol,
+                differences,
+                current,
+            });
+        }
+    }
+    drifts
+}
+
+fn compare_metadata(recorded: &TokenMetadata, current: &TokenMetadata) -> Vec<String> {
+    fn show<T: std::fmt::Display>(value: &Option<T>) -> String {
+        value
+            .as_ref()
+            .map_or("not recorded".to_string(), |v| v.to_string())
+    }
+
+    let mut differences = Vec::new();
+    if recorded.symbol != current.symbol {
+        differences.push(format!("symbol {} -> {}", recorded.symbol, current.symbol));
+    }
+    if recorded.decimals != current.decimals {
+        differences.push(format!(
+            "decimals {} -> {}",
+            show(&recorded.decimals),
+            show(&current.decimals)
+        ));
+    }
+    if recorded.token_name != current.token_name {
+        differences.push(format!(
+            "name {} -> {}",
+            show(&recorded.token_name),
+            show(&current.token_name)
+        ));
+    }
+    if recorded.token_type != current.token_type {
+        differences.push(format!(
+            "type {} -> {}",
+            recorded.token_type, current.token_type
+        ));
+    }
+    if recorded.code_hash != current.code_hash {
+        differences.push(format!(
+            "bytecode hash {} -> {}",
+            show(&recorded.code_hash),
+            show(&current.code_hash)
+        ));
+    }
+    if recorded.implementation != current.implementation {
+        differences.push(format!(
+            "proxy implementation {} -> {}",
+            show(&recorded.implementation),
+            show(&current.implementation)
+        ));
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> WhitelistExport {
+        WhitelistExport {
+            wallets: vec![
+                ExportedWallet {
+                    address: "0x1111111111111111111111111111111111111111".to_string(),
+                    name: Some("ops, \"hot\" wallet".to_string()),
+                    chain_id: None,
+                },
+                ExportedWallet {
+                    address: "0x2222222222222222222222222222222222222222".to_string(),
+                    name: None,
+                    chain_id: Some(42161),
+                },
+            ],
+            tokens: vec![ExportedToken {
+                address: "0x3333333333333333333333333333333333333333".to_string(),
+                chain_id: 1,
+                symbol: "USDC".to_string(),
+                name: Some("USD Coin, bridged".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let csv = sample().render(ExportFormat::Csv).unwrap();
+        assert!(csv.starts_with(&format!("{}\n", CSV_HEADER)));
+        assert!(csv.contains(
+            "wallet,0x1111111111111111111111111111111111111111,all,\"ops, \"\"hot\"\" wallet\","
+        ));
+
+        let parsed = WhitelistExport::parse(&csv, ExportFormat::Csv).unwrap();
+        assert_eq!(parsed.wallets.len(), 2);
+        assert_eq!(
+            parsed.wallets[0].name.as_deref(),
+            Some("ops, \"hot\" wallet")
+        );
+        assert_eq!(parsed.wallets[0].chain_id, None);
+        assert_eq!(parsed.wallets[1].name, None);
+        assert_eq!(parsed.wallets[1].chain_id, Some(42161));
+        assert_eq!(parsed.tokens.len(), 1);
+        assert_eq!(parsed.tokens[0].name.as_deref(), Some("USD Coin, bridged"));
+        assert_eq!(parsed.tokens[0].symbol, "USDC");
+        assert_eq!(parsed.tokens[0].chain_id, 1);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = sample().render(ExportFormat::Json).unwrap();
+        let parsed = WhitelistExport::parse(&json, ExportFormat::Json).unwrap();
+        assert_eq!(parsed.wallets[0].name, sample().wallets[0].name);
+        assert_eq!(parsed.tokens[0].address, sample().tokens[0].address);
+    }
+
+    #[test]
+    fn csv_header_is_only_skipped_on_the_first_line() {
+        let without_header = "wallet,0x1111111111111111111111111111111111111111,1,ops,\n";
+        let parsed = WhitelistExport::parse(without_header, ExportFormat::Csv).unwrap();
+        assert_eq!(parsed.wallets.len(), 1);
+
+        let repeated_header = format!("{}\n{}\n", CSV_HEADER, CSV_HEADER);
+        assert!(WhitelistExport::parse(&repeated_header, ExportFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn split_csv_line_unquotes_fields() {
+        assert_eq!(
+            split_csv_line("a, \"b, c\" ,\"d \"\"e\"\"\",").unwrap(),
+            vec!["a", "b, c", "d \"e\"", ""]
+        );
+    }
+
+    #[test]
+    fn unterminated_quote_is_an_error() {
+        assert!(split_csv_line("wallet,0x1,1,\"ops,").is_err());
+        let error = WhitelistExport::parse("wallet,0x1,1,\"ops,\n", ExportFormat::Csv)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("line 1"), "{}", error);
+    }
+}